@@ -0,0 +1,236 @@
+use std::collections::{HashSet, VecDeque};
+
+use specs::prelude::*;
+use specs::Join;
+
+use crate::components;
+use crate::camera::Camera;
+use crate::tuning::GameMode;
+
+pub const ACHIEVEMENT_FILE: &str = "achievements.txt";
+//How long an unlock stays on screen
+pub const TOAST_DURATION: f64 = 4.0;
+//Asteroids on screen that count as a full field and how long to survive in one
+const ASTEROID_FIELD_COUNT: usize = 1000;
+const ASTEROID_FIELD_SURVIVAL: f64 = 30.0;
+//Shots a wave needs before its accuracy counts
+const SHARPSHOOTER_MIN_SHOTS: u32 = 20;
+const SHARPSHOOTER_ACCURACY: f64 = 0.9;
+//Waves before this are too small for a no damage clear to mean anything
+const UNTOUCHABLE_MIN_LEVEL: u32 = 3;
+
+//Things that happen during play, pushed by whoever notices them and drained by AchievementTracker every frame
+pub enum GameEvent {
+    RunStarted,
+    AsteroidDestroyed,
+    EnemyDestroyed,
+    BossDefeated,
+    //A player projectile left the barrel, and the first time one hit anything
+    ShotFired,
+    ShotHit,
+    PlayerDamaged,
+    WaveCleared(u32),
+    LevelReached(u32),
+}
+
+#[derive(Default)]
+pub struct GameEvents {
+    pub events: Vec<GameEvent>,
+}
+
+impl GameEvents {
+    pub fn push(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+}
+
+pub struct Achievement {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+pub const ALL_ACHIEVEMENTS: [Achievement; 10] = [
+    Achievement { id: "first_rock", name: "First Rock", description: "Destroy an asteroid" },
+    Achievement { id: "rock_breaker", name: "Rock Breaker", description: "Destroy 100 asteroids" },
+    Achievement { id: "demolition_expert", name: "Demolition Expert", description: "Destroy 1000 asteroids" },
+    Achievement { id: "ufo_hunter", name: "UFO Hunter", description: "Destroy a UFO" },
+    Achievement { id: "mothership_down", name: "Mothership Down", description: "Defeat a mothership" },
+    Achievement { id: "level_5", name: "Getting Somewhere", description: "Reach level 5" },
+    Achievement { id: "level_10", name: "Veteran", description: "Reach level 10" },
+    Achievement { id: "untouchable", name: "Untouchable", description: "Clear a wave without taking damage" },
+    Achievement { id: "sharpshooter", name: "Sharpshooter", description: "Clear a wave with 90% accuracy" },
+    Achievement { id: "asteroid_field", name: "Asteroid Field", description: "Survive 30 seconds with 1000 asteroids around" },
+];
+
+//Unlocks and lifetime stats kept between sessions, plus what the current run and wave have done so far
+#[derive(Default)]
+pub struct Achievements {
+    pub unlocked: HashSet<String>,
+    pub asteroids_destroyed: u32,
+    wave_shots: u32,
+    wave_hits: u32,
+    wave_damaged: bool,
+    field_time: f64,
+    //Set once the player has been invincible at any point of the run, nothing counts for the rest of it
+    run_invincible: bool,
+    //Unlocks waiting to be shown, the front one is on screen with its remaining time
+    pub toasts: VecDeque<(&'static Achievement, f64)>,
+    //Set whenever the toast on screen changes so main can rebuild the HUD
+    pub toast_changed: bool,
+}
+
+impl Achievements {
+    //One line per unlocked achievement id and a line with the lifetime asteroid count. A missing file means nothing is unlocked yet
+    pub fn load(path: &str) -> Achievements {
+        let mut achievements = Achievements::default();
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return achievements;
+        };
+        for line in text.lines() {
+            match line.trim().split_once(' ') {
+                Some(("asteroids_destroyed", count)) => achievements.asteroids_destroyed = count.trim().parse().unwrap_or(0),
+                Some(("unlocked", id)) => { achievements.unlocked.insert(id.trim().to_string()); },
+                _ => {}
+            }
+        }
+        achievements
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut text = format!("asteroids_destroyed {}\n", self.asteroids_destroyed);
+        for achievement in &ALL_ACHIEVEMENTS {
+            if self.unlocked.contains(achievement.id) {
+                text += &format!("unlocked {}\n", achievement.id);
+            }
+        }
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    //Returns true the first time an achievement is unlocked
+    fn unlock(&mut self, id: &str) -> bool {
+        let Some(achievement) = ALL_ACHIEVEMENTS.iter().find(|achievement| achievement.id == id) else {
+            return false;
+        };
+        if !self.unlocked.insert(id.to_string()) {
+            return false;
+        }
+        if self.toasts.is_empty() {
+            self.toast_changed = true;
+        }
+        self.toasts.push_back((achievement, TOAST_DURATION));
+        true
+    }
+
+    fn start_wave(&mut self) {
+        self.wave_shots = 0;
+        self.wave_hits = 0;
+        self.wave_damaged = false;
+    }
+}
+
+pub struct AchievementTracker;
+
+impl<'a> System<'a> for AchievementTracker {
+    type SystemData = (
+        Write<'a, GameEvents>,
+        Write<'a, Achievements>,
+        ReadStorage<'a, components::Asteroid>,
+        ReadStorage<'a, components::Player>,
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::GameData>,
+        Read<'a, Camera>,
+        Read<'a, crate::DeltaTime>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (mut events, mut achievements, asteroids, players, positions, game_data, camera, delta_time) = data;
+        let delta_time = delta_time.0;
+        //Unlocks and cleared waves are saved straight away so lifetime stats survive a crash
+        let mut must_save = false;
+
+        if events.events.iter().any(|event| matches!(event, GameEvent::RunStarted)) {
+            achievements.start_wave();
+            achievements.field_time = 0.0;
+            achievements.run_invincible = false;
+        }
+        //Zen runs are invincible by design and never count either
+        if (&game_data).join().any(|data| data.invincible_player || data.mode == GameMode::Zen) {
+            achievements.run_invincible = true;
+        }
+        if achievements.run_invincible {
+            events.events.clear();
+            achievements.field_time = 0.0;
+        }
+
+        for event in events.events.drain(..) {
+            match event {
+                GameEvent::RunStarted => {}
+                GameEvent::AsteroidDestroyed => {
+                    achievements.asteroids_destroyed += 1;
+                    must_save |= achievements.unlock("first_rock");
+                    if achievements.asteroids_destroyed >= 100 {
+                        must_save |= achievements.unlock("rock_breaker");
+                    }
+                    if achievements.asteroids_destroyed >= 1000 {
+                        must_save |= achievements.unlock("demolition_expert");
+                    }
+                }
+                GameEvent::EnemyDestroyed => must_save |= achievements.unlock("ufo_hunter"),
+                GameEvent::BossDefeated => must_save |= achievements.unlock("mothership_down"),
+                GameEvent::ShotFired => achievements.wave_shots += 1,
+                GameEvent::ShotHit => achievements.wave_hits += 1,
+                GameEvent::PlayerDamaged => achievements.wave_damaged = true,
+                GameEvent::WaveCleared(level) => {
+                    must_save = true;
+                    if level >= UNTOUCHABLE_MIN_LEVEL && !achievements.wave_damaged {
+                        must_save |= achievements.unlock("untouchable");
+                    }
+                    let accuracy = achievements.wave_hits.min(achievements.wave_shots) as f64 / achievements.wave_shots.max(1) as f64;
+                    if achievements.wave_shots >= SHARPSHOOTER_MIN_SHOTS && accuracy >= SHARPSHOOTER_ACCURACY {
+                        must_save |= achievements.unlock("sharpshooter");
+                    }
+                }
+                GameEvent::LevelReached(level) => {
+                    achievements.start_wave();
+                    if level >= 5 {
+                        must_save |= achievements.unlock("level_5");
+                    }
+                    if level >= 10 {
+                        must_save |= achievements.unlock("level_10");
+                    }
+                }
+            }
+        }
+
+        //Time spent alive among a full asteroid field
+        let on_screen = (&positions, &asteroids).join()
+            .filter(|(pos, _)| {
+                let (x, y) = camera.world_to_screen(pos.x, pos.y);
+                x >= 0.0 && y >= 0.0 && x < crate::SCREEN_WIDTH as f64 && y < crate::SCREEN_HEIGHT as f64
+            })
+            .count();
+        if !achievements.run_invincible && players.join().next().is_some() && on_screen >= ASTEROID_FIELD_COUNT {
+            achievements.field_time += delta_time;
+            if achievements.field_time >= ASTEROID_FIELD_SURVIVAL {
+                must_save |= achievements.unlock("asteroid_field");
+            }
+        } else {
+            achievements.field_time = 0.0;
+        }
+
+        //Only the front toast counts down, the rest wait their turn
+        if let Some((_, remaining)) = achievements.toasts.front_mut() {
+            *remaining -= delta_time;
+            if *remaining <= 0.0 {
+                achievements.toasts.pop_front();
+                achievements.toast_changed = true;
+            }
+        }
+
+        if must_save {
+            if let Err(e) = achievements.save(ACHIEVEMENT_FILE) {
+                eprintln!("Could not save achievements: {}", e);
+            }
+        }
+    }
+}
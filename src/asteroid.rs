@@ -2,50 +2,41 @@ use specs::{System, WriteStorage, Join};
 use specs::prelude::Entities;
 use specs::prelude::*;
 
-use crate::{components, game};
+use rand::Rng;
+
+use crate::{components, damage, explosion, game, movement, pickup};
+use crate::components::AsteroidVariant;
+use crate::camera::Camera;
+use crate::tuning::Tuning;
+use crate::achievements::GameEvents;
 pub struct AsteroidMovement;
 
+//Size range of asteroids spawned at the start of a wave
+pub const ASTEROID_MIN_SIZE: u32 = 40;
+pub const ASTEROID_MAX_SIZE: u32 = 110;
+//Asteroids smaller than this are destroyed when shot instead of splitting,
+//so the halved fragments never come out smaller than a spawned asteroid
+pub const MIN_SPLIT_SIZE: u32 = 2 * ASTEROID_MIN_SIZE;
+//Hits an armoured asteroid takes before it breaks
+pub const ARMOURED_HEALTH: i32 = 3;
+//Blast left by an explosive asteroid, big enough to set off the ones next to it
+const EXPLOSIVE_BLAST_RADIUS: f64 = 170.0;
+const EXPLOSIVE_BLAST_DAMAGE: i32 = 2;
+//Magnetic asteroids accelerate towards the nearest ship within range, up to a top speed
+const MAGNETIC_PULL: f64 = 90.0;
+const MAGNETIC_RANGE: f64 = 1500.0;
+const MAGNETIC_MAX_SPEED: f64 = 260.0;
+
 pub struct AsteroidCollider;
 
 impl<'a> System<'a> for AsteroidMovement {
     type SystemData = (
-        WriteStorage<'a, components::Position>,
-        WriteStorage<'a, components::Renderable>,
+        ReadStorage<'a, components::Position>,
         WriteStorage<'a, components::Asteroid>,
-        Read<'a, crate::DeltaTime>,
     );
     fn run (&mut self, mut data: Self::SystemData) {
-        let delta_time = data.3 .0;
-        for (pos, renderable, asteroid) in (&mut data.0, &mut data.1, &mut data.2).join() {
-            let radians = pos.rot.to_radians();
-
-            pos.x += asteroid.speed * delta_time * radians.sin();
-            pos.y -= asteroid.speed * delta_time * radians.cos();
-
-            let half_width = (renderable.output_width / 2) as u32;
-            let half_height = (renderable.output_height / 2) as u32;
-
-            if pos.x > (crate::SCREEN_WIDTH - half_width).into() || pos.x < half_width.into() {
-                pos.rot = 360.0 - pos.rot;
-            }
-            else if pos.y > (crate::SCREEN_HEIGHT - half_height).into() || pos.y < half_height.into() {
-                if pos.rot > 180.0 {
-                    pos.rot = 540.0 - pos.rot;
-                }
-                else
-                {
-                    pos.rot = 180.0 - pos.rot;
-                }
-            }
-            renderable.img_rotation += asteroid.rotation_speed * delta_time;
-
-            if renderable.img_rotation > 360.0 {
-                renderable.img_rotation -= 360.0;
-            }
-            if renderable.img_rotation < 360.0 {
-                renderable.img_rotation += 360.0;
-            }
-            asteroid.quadrant = game::get_current_quadrant(&pos);
+        for (pos, asteroid) in (&data.0, &mut data.1).join() {
+            asteroid.quadrant = game::get_current_quadrant(pos);
         }
     }
 }
@@ -56,17 +47,29 @@ impl<'a> System<'a> for AsteroidCollider {
         WriteStorage<'a, components::Renderable>,
         WriteStorage<'a, components::Player>,
         WriteStorage<'a, components::Asteroid>,
+        ReadStorage<'a, components::PowerUps>,
+        WriteStorage<'a, components::Velocity>,
         ReadStorage<'a, components::GameData>,
-        Entities<'a>
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        Write<'a, Camera>,
+        ReadExpect<'a, Tuning>,
+        Write<'a, GameEvents>,
     );
     fn run (&mut self, data: Self::SystemData) {
-        let (positions, renderables, mut player, asteroids, game_data, entities) = data;
+        let (positions, renderables, mut player, asteroids, power_ups, mut velocities, game_data, entities, lazy, mut camera, tuning, mut events) = data;
         for data in (&game_data).join() {
             //early out asap if player is invincible
             if data.invincible_player { return; }
 
-            for (player_pos, player_renderable, player, player_entity) in (&positions, &renderables, &mut player, &entities).join() {
-                let player_quadrant = game::get_current_quadrant(&player_pos);
+            for (player_pos, player_renderable, player, power_ups, mut velocity, player_entity) in (&positions, &renderables, &mut player, power_ups.maybe(), (&mut velocities).maybe(), &entities).join() {
+                let shielded = power_ups.is_some_and(|power_ups| power_ups.shield > 0.0);
+                //A blinking ship passes through asteroids untouched
+                if player.invulnerable > 0.0 && !shielded {
+                    continue;
+                }
+
+                let player_quadrant = game::get_current_quadrant(player_pos);
 
                 for (asteroid_pos, asteroid_renderable, asteroid, asteroid_entity) in (&positions, &renderables, &asteroids, &entities).join() {
                     if asteroid.quadrant == player_quadrant
@@ -79,10 +82,14 @@ impl<'a> System<'a> for AsteroidCollider {
                         if hypotenuse < (asteroid_renderable.output_width + player_renderable.output_width) as f64 / 2.0 {
                             //println!("Collision");
                             entities.delete(asteroid_entity).ok();
-                            player.health -= 1;
-
-                            if player.health < 1 {
-                                entities.delete(player_entity).ok();
+                            if asteroid.variant == AsteroidVariant::Explosive {
+                                explode(&lazy, &entities, asteroid_pos, None);
+                            }
+                            //The shield smashes the asteroid without hurting the ship
+                            let target = damage::PlayerTarget { player: &mut *player, entity: player_entity, pos: player_pos, velocity: velocity.as_deref_mut(), shielded };
+                            damage::damage_player(target, (asteroid_pos.x, asteroid_pos.y), &entities, &mut camera, &tuning, &mut events);
+                            if player.invulnerable > 0.0 && !shielded {
+                                break;
                             }
                         }
                     }
@@ -90,4 +97,161 @@ impl<'a> System<'a> for AsteroidCollider {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn build_asteroid<B: Builder>(builder: B, x: f64, y: f64, heading: f64, size: u32, speed: f64, rotation_speed: f64, boundary: components::BoundaryBehavior, variant: AsteroidVariant) -> B {
+    let position = components::Position { x, y, rot: 0.0 };
+    let quadrant = game::get_current_quadrant(&position);
+    let health = if variant == AsteroidVariant::Armoured { ARMOURED_HEALTH } else { 1 };
+
+    let builder = builder
+        .with(position)
+        .with(components::Renderable {
+            texture_name: texture_name(variant, health),
+            img_width: 215,
+            img_height: 215,
+            output_width: size,
+            output_height: size,
+            img_rotation: 0.0,
+            visible: true
+        })
+        .with(components::Asteroid{
+            quadrant,
+            size,
+            variant,
+            health
+        })
+        .with(movement::velocity_from_heading(heading, speed))
+        .with(components::AngularVelocity { speed: rotation_speed })
+        .with(boundary);
+
+    //Magnetic asteroids need somewhere to put their pull and a cap so it cannot run away
+    if variant == AsteroidVariant::Magnetic {
+        builder
+            .with(components::Acceleration::default())
+            .with(components::SpeedLimit { max_speed: MAGNETIC_MAX_SPEED.max(speed) })
+    } else {
+        builder
+    }
+}
+
+//Breaks a shot asteroid into two or three smaller, faster pieces flying apart from its heading.
+//Returns false when the asteroid is too small to split and should just be destroyed
+pub fn split_asteroid(lazy: &LazyUpdate, entities: &Entities, pos: &components::Position, velocity: &components::Velocity, size: u32, boundary: components::BoundaryBehavior) -> bool {
+    if size < MIN_SPLIT_SIZE {
+        return false;
+    }
+
+    let mut rng = rand::thread_rng();
+    let fragment_count = if size >= 90 { 3 } else { 2 };
+    let fragment_size = size / 2;
+    let speed = (velocity.x * velocity.x + velocity.y * velocity.y).sqrt();
+    let heading = movement::rotation_of(velocity.x, velocity.y);
+
+    //Spread fragments evenly across a fan centred on the parent heading
+    let fan = 80.0;
+    for i in 0..fragment_count {
+        let offset = -fan / 2.0 + fan * i as f64 / (fragment_count - 1) as f64;
+        let fragment_heading = heading + offset + rng.gen_range(-10.0..10.0);
+        let fragment_speed = speed * rng.gen_range(1.3..1.7);
+        let rotation_speed = rng.gen_range(-500.0..500.0);
+
+        //Start a little apart so the pieces do not sit on top of each other
+        let (dir_x, dir_y) = movement::heading(fragment_heading);
+        let x = pos.x + dir_x * fragment_size as f64 / 2.0;
+        let y = pos.y + dir_y * fragment_size as f64 / 2.0;
+
+        build_asteroid(lazy.create_entity(entities), x, y, fragment_heading, fragment_size, fragment_speed, rotation_speed, boundary, AsteroidVariant::Plain)
+        .build();
+    }
+    true
+}
+
+//Takes damage off an asteroid and shows the cracks on armoured ones. Returns true once it is destroyed
+pub fn damage_asteroid(asteroid: &mut components::Asteroid, renderable: &mut components::Renderable, damage: i32) -> bool {
+    asteroid.health -= damage;
+    if asteroid.health < 1 {
+        return true;
+    }
+    renderable.texture_name = texture_name(asteroid.variant, asteroid.health);
+    false
+}
+
+//Splits a destroyed asteroid, the smallest ones have a chance to leave a pickup instead.
+//Explosive ones blow up rather than split, owner is credited for whatever the blast destroys
+pub fn break_asteroid(lazy: &LazyUpdate, entities: &Entities, pos: &components::Position, velocity: &components::Velocity, asteroid: &components::Asteroid, owner: Option<usize>, tuning: &Tuning) {
+    if asteroid.variant == AsteroidVariant::Explosive {
+        explode(lazy, entities, pos, owner);
+        return;
+    }
+    if !split_asteroid(lazy, entities, pos, velocity, asteroid.size, tuning.asteroid_boundary) {
+        pickup::maybe_drop_pickup(lazy, entities, pos.x, pos.y, tuning);
+    }
+}
+
+fn explode(lazy: &LazyUpdate, entities: &Entities, pos: &components::Position, owner: Option<usize>) {
+    explosion::build_explosion(lazy.create_entity(entities), pos.x, pos.y, EXPLOSIVE_BLAST_RADIUS, EXPLOSIVE_BLAST_DAMAGE, owner, true).build();
+}
+
+//Smaller asteroids are harder to hit and worth more
+pub fn score_for_size(size: u32, level: u32) -> u32 {
+    10 * level * ASTEROID_MAX_SIZE / size.max(1)
+}
+
+//Plain asteroids pick one of three shapes, armoured ones look more cracked the fewer hits they have left
+fn texture_name(variant: AsteroidVariant, health: i32) -> String {
+    match variant {
+        AsteroidVariant::Plain => get_random_asteroid_texture_name(),
+        AsteroidVariant::Armoured if health >= ARMOURED_HEALTH => String::from("Assets/Images/asteroid_armoured.png"),
+        AsteroidVariant::Armoured if health > 1 => String::from("Assets/Images/asteroid_armoured_cracked.png"),
+        AsteroidVariant::Armoured => String::from("Assets/Images/asteroid_armoured_broken.png"),
+        AsteroidVariant::Explosive => String::from("Assets/Images/asteroid_explosive.png"),
+        AsteroidVariant::Magnetic => String::from("Assets/Images/asteroid_magnetic.png"),
+    }
+}
+
+pub const VARIANT_TEXTURES: [&str; 5] = [
+    "Assets/Images/asteroid_armoured.png",
+    "Assets/Images/asteroid_armoured_cracked.png",
+    "Assets/Images/asteroid_armoured_broken.png",
+    "Assets/Images/asteroid_explosive.png",
+    "Assets/Images/asteroid_magnetic.png",
+];
+
+fn get_random_asteroid_texture_name() -> String {
+    let random_number = rand::thread_rng().gen_range(1..=3);
+    format!("Assets/Images/asteroid_{}.png", random_number)
+}
+
+//Pulls magnetic asteroids towards the nearest ship
+pub struct MagneticPull;
+
+impl<'a> System<'a> for MagneticPull {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::Asteroid>,
+        ReadStorage<'a, components::Player>,
+        WriteStorage<'a, components::Acceleration>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, asteroids, players, mut accelerations) = data;
+
+        let player_positions: Vec<(f64, f64)> = (&positions, &players).join().map(|(pos, _)| (pos.x, pos.y)).collect();
+        for (pos, asteroid, acceleration) in (&positions, &asteroids, &mut accelerations).join() {
+            if asteroid.variant != AsteroidVariant::Magnetic {
+                continue;
+            }
+            let nearest = player_positions.iter()
+                .map(|(x, y)| (x - pos.x, y - pos.y))
+                .min_by(|a, b| (a.0 * a.0 + a.1 * a.1).total_cmp(&(b.0 * b.0 + b.1 * b.1)));
+            if let Some((diff_x, diff_y)) = nearest {
+                let distance = (diff_x * diff_x + diff_y * diff_y).sqrt();
+                if distance > f64::EPSILON && distance < MAGNETIC_RANGE {
+                    acceleration.x += diff_x / distance * MAGNETIC_PULL;
+                    acceleration.y += diff_y / distance * MAGNETIC_PULL;
+                }
+            }
+        }
+    }
+}
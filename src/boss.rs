@@ -0,0 +1,377 @@
+use std::collections::HashSet;
+
+use specs::prelude::*;
+use specs::{Entities, Join};
+
+use crate::{components, damage, movement, weapon};
+use crate::components::{Boss, BossPart, Faction, WeaponKind, WeaponStats};
+use crate::camera::Camera;
+use crate::tuning::Tuning;
+use crate::achievements::{GameEvent, GameEvents};
+
+//Every this many levels the wave is a single boss instead of asteroids
+pub const BOSS_INTERVAL: u32 = 5;
+
+const CORE_SIZE: u32 = 220;
+const PART_SIZE: u32 = 64;
+const WEAK_POINT_HEALTH: i32 = 6;
+const WEAK_POINT_COUNT: usize = 4;
+const CORE_HEALTH: i32 = 30;
+//Distance the boss tries to keep from the player
+const HOLD_DISTANCE: f64 = 450.0;
+
+pub fn is_boss_level(level: u32) -> bool {
+    level.rem_euclid(BOSS_INTERVAL) == 0
+}
+
+pub fn spawn_boss(ecs: &mut World, x: f64, y: f64, level: u32) {
+    //Later bosses are tougher
+    let toughness = (level / BOSS_INTERVAL).max(1) as i32;
+    let weak_point_health = WEAK_POINT_HEALTH * toughness;
+    let core_health = CORE_HEALTH * toughness;
+    let max_health = weak_point_health * WEAK_POINT_COUNT as i32 + core_health;
+
+    let boss = ecs.create_entity()
+        .with(components::Position { x, y, rot: 0.0 })
+        .with(components::Renderable {
+            texture_name: String::from("Assets/Images/boss_core.png"),
+            img_width: 256,
+            img_height: 256,
+            output_width: CORE_SIZE,
+            output_height: CORE_SIZE,
+            img_rotation: 0.0,
+            visible: true
+        })
+        .with(Boss {
+            phase: 1,
+            health: max_health,
+            max_health,
+            core_health,
+            score_value: 5000,
+            attack_timer: 2.0,
+            spiral_angle: 0.0,
+            last_hit_by: None
+        })
+        .with(components::Velocity { x: 0.0, y: 0.0 })
+        .with(components::Acceleration::default())
+        .with(components::AngularVelocity { speed: 20.0 })
+        .with(components::Drag { damping: 1.0 })
+        .with(components::SpeedLimit { max_speed: 90.0 })
+        .with(components::BoundaryBehavior::Clamp)
+    .build();
+
+    //Weak points on the four sides with armour plates covering the gaps between them
+    for i in 0..WEAK_POINT_COUNT * 2 {
+        let weak_point = i % 2 == 0;
+        let (dir_x, dir_y) = movement::heading(i as f64 * 360.0 / (WEAK_POINT_COUNT * 2) as f64);
+        let radius = if weak_point { 125.0 } else { 115.0 };
+
+        ecs.create_entity()
+            .with(components::Position { x: x + dir_x * radius, y: y + dir_y * radius, rot: 0.0 })
+            .with(components::Renderable {
+                texture_name: String::from(if weak_point { "Assets/Images/boss_weak_point.png" } else { "Assets/Images/boss_armour.png" }),
+                img_width: 64,
+                img_height: 64,
+                output_width: PART_SIZE,
+                output_height: PART_SIZE,
+                img_rotation: 0.0,
+                visible: true
+            })
+            .with(BossPart {
+                boss,
+                offset_x: dir_x * radius,
+                offset_y: dir_y * radius,
+                weak_point,
+                health: weak_point_health
+            })
+        .build();
+    }
+}
+
+//Keeps every part attached to its boss, turning with it
+pub struct BossPartFollow;
+
+impl<'a> System<'a> for BossPartFollow {
+    type SystemData = (
+        WriteStorage<'a, components::Position>,
+        ReadStorage<'a, BossPart>,
+        Entities<'a>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (mut positions, parts, entities) = data;
+
+        for (part, part_entity) in (&parts, &entities).join() {
+            let boss_pos = match positions.get(part.boss) {
+                Some(pos) if entities.is_alive(part.boss) => (pos.x, pos.y, pos.rot),
+                _ => {
+                    entities.delete(part_entity).ok();
+                    continue;
+                }
+            };
+            let radians = boss_pos.2.to_radians();
+            let (sin, cos) = radians.sin_cos();
+
+            if let Some(pos) = positions.get_mut(part_entity) {
+                pos.x = boss_pos.0 + part.offset_x * cos - part.offset_y * sin;
+                pos.y = boss_pos.1 + part.offset_x * sin + part.offset_y * cos;
+                pos.rot = boss_pos.2;
+            }
+        }
+    }
+}
+
+pub struct BossAi;
+
+impl<'a> System<'a> for BossAi {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        WriteStorage<'a, components::Acceleration>,
+        WriteStorage<'a, components::AngularVelocity>,
+        WriteStorage<'a, Boss>,
+        ReadStorage<'a, components::Player>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        ReadExpect<'a, Tuning>,
+        Read<'a, crate::DeltaTime>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, mut accelerations, mut angular_velocities, mut bosses, players, entities, lazy, tuning, delta_time) = data;
+
+        let player_positions: Vec<(f64, f64)> = (&positions, &players).join().map(|(pos, _)| (pos.x, pos.y)).collect();
+
+        for (pos, acceleration, angular_velocity, boss) in (&positions, &mut accelerations, &mut angular_velocities, &mut bosses).join() {
+            let target = player_positions.iter()
+                .map(|(x, y)| (x - pos.x, y - pos.y))
+                .min_by(|a, b| (a.0 * a.0 + a.1 * a.1).total_cmp(&(b.0 * b.0 + b.1 * b.1)));
+            let (to_x, to_y) = match target {
+                Some(offset) => offset,
+                None => continue,
+            };
+            let distance = (to_x * to_x + to_y * to_y).sqrt().max(1.0);
+
+            //Drift to hold distance, pressing in harder once the core is exposed
+            let hold_distance = if boss.phase >= 3 { HOLD_DISTANCE * 0.6 } else { HOLD_DISTANCE };
+            let push = ((distance - hold_distance) / hold_distance).clamp(-1.0, 1.0) * 200.0;
+            acceleration.x += to_x / distance * push;
+            acceleration.y += to_y / distance * push;
+
+            angular_velocity.speed = match boss.phase {
+                1 => 20.0,
+                2 => 45.0,
+                _ => 80.0,
+            };
+
+            boss.attack_timer -= delta_time.0;
+            if boss.attack_timer > 0.0 {
+                continue;
+            }
+
+            let aim = movement::rotation_of(to_x, to_y);
+            let (angles, speed, delay): (Vec<f64>, f64, f64) = match boss.phase {
+                //Radial burst all around
+                1 => ((0..16).map(|i| i as f64 * 22.5).collect(), 300.0, 2.2),
+                //Aimed fan at the player
+                2 => ((-2..=2).map(|i| aim + i as f64 * 10.0).collect(), 420.0, 1.0),
+                //Twin spiral
+                _ => {
+                    boss.spiral_angle += 17.0;
+                    (vec![boss.spiral_angle, boss.spiral_angle + 180.0], 360.0, 0.12)
+                }
+            };
+            boss.attack_timer = delay;
+
+            let stats = boss_shot(speed);
+            for angle in angles {
+                let origin = components::Position { x: pos.x, y: pos.y, rot: angle };
+                weapon::spawn_projectile(&lazy, &entities, &origin, 0.0, &stats, Faction::Enemy, None, &tuning);
+            }
+        }
+    }
+}
+
+fn boss_shot(speed: f64) -> WeaponStats {
+    WeaponStats {
+        kind: WeaponKind::Single,
+        name: "Boss Laser",
+        fire_rate: 1.0,
+        projectile: weapon::ENEMY_LASER,
+        projectile_speed: speed,
+        projectiles_per_shot: 1,
+        spread: 0.0,
+        burst_count: 1,
+        burst_interval: 0.0
+    }
+}
+
+//Only weak points take damage, armour soaks it up. Returns true when this destroys the weak point
+pub fn damage_part(part: &mut BossPart, damage: i32) -> bool {
+    if !part.weak_point || part.health < 1 {
+        return false;
+    }
+    part.health -= damage;
+    part.health < 1
+}
+
+//The core only takes damage once every weak point is destroyed, before that it blocks it
+pub fn damage_core(boss: &mut Boss, damage: i32, owner: Option<usize>) {
+    if boss.phase >= 3 {
+        boss.core_health -= damage;
+        boss.last_hit_by = owner;
+    }
+}
+
+//Player shots against the boss. Weak points and the exposed core take damage, armour soaks shots up.
+//Also works out the phase and removes the boss once the core is destroyed, by shots or by blasts
+pub struct BossDamage;
+
+impl<'a> System<'a> for BossDamage {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::Renderable>,
+        WriteStorage<'a, components::Laser>,
+        WriteStorage<'a, Boss>,
+        WriteStorage<'a, BossPart>,
+        WriteStorage<'a, components::GameData>,
+        Entities<'a>,
+        Write<'a, Camera>,
+        Write<'a, GameEvents>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, renderables, mut lasers, mut bosses, mut parts, mut game_data, entities, mut camera, mut events) = data;
+
+        let mut spent_lasers = HashSet::new();
+
+        for (laser_pos, laser, laser_entity) in (&positions, &mut lasers, &entities).join() {
+            if laser.faction != Faction::Player {
+                continue;
+            }
+            for (part_pos, part_renderable, part, part_entity) in (&positions, &renderables, &mut parts, &entities).join() {
+                if part.health < 1 || !touches(laser_pos, part_pos, part_renderable.output_width) {
+                    continue;
+                }
+                if !laser.hit {
+                    laser.hit = true;
+                    events.push(GameEvent::ShotHit);
+                }
+                if damage_part(part, 1) {
+                    entities.delete(part_entity).ok();
+                    camera.add_trauma(0.3);
+                }
+                entities.delete(laser_entity).ok();
+                spent_lasers.insert(laser_entity);
+                break;
+            }
+        }
+
+        //Weak points still standing per boss
+        let mut weak_points: Vec<(Entity, i32, usize)> = Vec::new();
+        for part in (&parts).join() {
+            if !part.weak_point || part.health < 1 {
+                continue;
+            }
+            match weak_points.iter_mut().find(|(boss, _, _)| *boss == part.boss) {
+                Some(entry) => {
+                    entry.1 += part.health;
+                    entry.2 += 1;
+                },
+                None => weak_points.push((part.boss, part.health, 1)),
+            }
+        }
+
+        for (boss_pos, boss_renderable, boss, boss_entity) in (&positions, &renderables, &mut bosses, &entities).join() {
+            let (weak_health, weak_count) = weak_points.iter()
+                .find(|(boss, _, _)| *boss == boss_entity)
+                .map_or((0, 0), |(_, health, count)| (*health, *count));
+
+            boss.phase = if weak_count == 0 {
+                3
+            } else if weak_count <= WEAK_POINT_COUNT / 2 {
+                2
+            } else {
+                1
+            };
+
+            //Whoever lands the final hit on the core gets the score
+            for (laser_pos, laser, laser_entity) in (&positions, &mut lasers, &entities).join() {
+                if laser.faction != Faction::Player || spent_lasers.contains(&laser_entity) {
+                    continue;
+                }
+                if touches(laser_pos, boss_pos, boss_renderable.output_width) {
+                    if !laser.hit {
+                        laser.hit = true;
+                        events.push(GameEvent::ShotHit);
+                    }
+                    damage_core(boss, 1, laser.owner);
+                    entities.delete(laser_entity).ok();
+                    spent_lasers.insert(laser_entity);
+                }
+            }
+
+            boss.health = weak_health + boss.core_health.max(0);
+            if boss.core_health < 1 {
+                entities.delete(boss_entity).ok();
+                events.push(GameEvent::BossDefeated);
+                camera.add_trauma(1.0);
+                camera.zoom_pulse(1.15, 0.6);
+                for data in (&mut game_data).join() {
+                    data.add_score(boss.last_hit_by, boss.score_value * data.level);
+                }
+            }
+        }
+    }
+}
+
+//Ramming the boss hurts, the core and its parts are solid
+pub struct BossCollider;
+
+impl<'a> System<'a> for BossCollider {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::Renderable>,
+        WriteStorage<'a, components::Player>,
+        ReadStorage<'a, components::PowerUps>,
+        WriteStorage<'a, components::Velocity>,
+        ReadStorage<'a, Boss>,
+        ReadStorage<'a, BossPart>,
+        ReadStorage<'a, components::GameData>,
+        Entities<'a>,
+        Write<'a, Camera>,
+        ReadExpect<'a, Tuning>,
+        Write<'a, GameEvents>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, renderables, mut players, power_ups, mut velocities, bosses, parts, game_data, entities, mut camera, tuning, mut events) = data;
+
+        if (&game_data).join().any(|data| data.invincible_player) {
+            return;
+        }
+
+        for (boss_pos, boss_renderable, _) in (&positions, &renderables, bosses.mask() | parts.mask()).join() {
+            for (player_pos, player_renderable, player, power_ups, velocity, player_entity) in (&positions, &renderables, &mut players, power_ups.maybe(), &mut velocities, &entities).join() {
+                let reach = (boss_renderable.output_width + player_renderable.output_width) as f64 / 2.0;
+                let diff_x = player_pos.x - boss_pos.x;
+                let diff_y = player_pos.y - boss_pos.y;
+                let distance = (diff_x * diff_x + diff_y * diff_y).sqrt();
+                if distance >= reach {
+                    continue;
+                }
+
+                let shielded = power_ups.is_some_and(|power_ups| power_ups.shield > 0.0);
+                let target = damage::PlayerTarget { player, entity: player_entity, pos: player_pos, velocity: Some(&mut *velocity), shielded };
+                if !damage::damage_player(target, (boss_pos.x, boss_pos.y), &entities, &mut camera, &tuning, &mut events) {
+                    //Still push the ship off when the hit is ignored so it cannot sit inside the boss
+                    let (away_x, away_y) = if distance > f64::EPSILON { (diff_x / distance, diff_y / distance) } else { (0.0, -1.0) };
+                    velocity.x = away_x * tuning.knockback_speed;
+                    velocity.y = away_y * tuning.knockback_speed;
+                }
+            }
+        }
+    }
+}
+
+fn touches(point: &components::Position, center: &components::Position, size: u32) -> bool {
+    let radius = size as f64 / 2.0;
+    let diff_x = point.x - center.x;
+    let diff_y = point.y - center.y;
+    diff_x * diff_x + diff_y * diff_y < radius * radius
+}
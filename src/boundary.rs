@@ -0,0 +1,76 @@
+use specs::prelude::*;
+use specs::{Entities, Join};
+
+use crate::components;
+use crate::components::BoundaryBehavior;
+
+pub struct Boundary;
+
+impl<'a> System<'a> for Boundary {
+    type SystemData = (
+        WriteStorage<'a, components::Position>,
+        WriteStorage<'a, components::Velocity>,
+        ReadStorage<'a, components::Renderable>,
+        ReadStorage<'a, components::BoundaryBehavior>,
+        Entities<'a>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (mut positions, mut velocities, renderables, behaviors, entities) = data;
+        let width = crate::WORLD_WIDTH as f64;
+        let height = crate::WORLD_HEIGHT as f64;
+
+        for (pos, velocity, renderable, behavior, entity) in (&mut positions, (&mut velocities).maybe(), renderables.maybe(), &behaviors, &entities).join() {
+            let (half_width, half_height) = renderable.map_or((0.0, 0.0), |r| (r.output_width as f64 / 2.0, r.output_height as f64 / 2.0));
+
+            match behavior {
+                BoundaryBehavior::Wrap => {
+                    pos.x = pos.x.rem_euclid(width);
+                    pos.y = pos.y.rem_euclid(height);
+                },
+                BoundaryBehavior::Bounce => {
+                    if let Some(velocity) = velocity {
+                        bounce_axis(&mut pos.x, &mut velocity.x, half_width, width);
+                        bounce_axis(&mut pos.y, &mut velocity.y, half_height, height);
+                    }
+                },
+                BoundaryBehavior::Despawn => {
+                    if pos.x > width || pos.x < 0.0 || pos.y > height || pos.y < 0.0 {
+                        entities.delete(entity).ok();
+                    }
+                },
+                BoundaryBehavior::Clamp => {
+                    let clamped_x = pos.x.clamp(half_width, (width - half_width).max(half_width));
+                    let clamped_y = pos.y.clamp(half_height, (height - half_height).max(half_height));
+
+                    //Stop pushing into the edge so the entity does not stick to it
+                    if let Some(velocity) = velocity {
+                        if clamped_x != pos.x {
+                            velocity.x = 0.0;
+                        }
+                        if clamped_y != pos.y {
+                            velocity.y = 0.0;
+                        }
+                    }
+                    pos.x = clamped_x;
+                    pos.y = clamped_y;
+                },
+            }
+        }
+    }
+}
+
+//Reflects velocity off the edge and mirrors the overshoot back inside, so an entity
+//that crossed the edge can never be flipped again next frame while still outside
+fn bounce_axis(pos: &mut f64, velocity: &mut f64, half_extent: f64, limit: f64) {
+    let min = half_extent;
+    let max = (limit - half_extent).max(min);
+
+    if *pos < min {
+        *pos = (2.0 * min - *pos).min(max);
+        *velocity = velocity.abs();
+    }
+    else if *pos > max {
+        *pos = (2.0 * max - *pos).max(min);
+        *velocity = -velocity.abs();
+    }
+}
@@ -0,0 +1,220 @@
+use rand::Rng;
+use specs::prelude::*;
+use specs::Join;
+
+use crate::components;
+
+//Centre of the view in world coordinates
+pub struct Camera {
+    pub x: f64,
+    pub y: f64,
+    //How quickly the camera catches up with its target, per second
+    pub smoothing: f64,
+    //Half size of the box around the centre the target can move in without the camera following
+    pub dead_zone_width: f64,
+    pub dead_zone_height: f64,
+    //Shake, zoom and kick are skipped entirely when false
+    pub effects_enabled: bool,
+    //0..1, shake strength grows with the square of it and it decays over time
+    trauma: f64,
+    shake_x: f64,
+    shake_y: f64,
+    kick_x: f64,
+    kick_y: f64,
+    zoom: f64,
+    //Zoomed out below 1 to keep every co-op ship in view, the zoom pulse is applied on top
+    fit_zoom: f64,
+    zoom_peak: f64,
+    zoom_duration: f64,
+    zoom_timer: f64,
+}
+
+const MAX_SHAKE_OFFSET: f64 = 40.0;
+const TRAUMA_DECAY: f64 = 1.5;
+//Per second rate the kick offset returns to zero
+const KICK_RECOVERY: f64 = 10.0;
+//Space kept between the outermost ships and the edge of the view
+const FIT_MARGIN: f64 = 250.0;
+//Per second rate the camera zooms to fit the ships
+const FIT_SMOOTHING: f64 = 3.0;
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            x: crate::SCREEN_WIDTH as f64 / 2.0,
+            y: crate::SCREEN_HEIGHT as f64 / 2.0,
+            smoothing: 4.0,
+            dead_zone_width: 150.0,
+            dead_zone_height: 100.0,
+            effects_enabled: true,
+            trauma: 0.0,
+            shake_x: 0.0,
+            shake_y: 0.0,
+            kick_x: 0.0,
+            kick_y: 0.0,
+            zoom: 1.0,
+            fit_zoom: 1.0,
+            zoom_peak: 1.0,
+            zoom_duration: 0.0,
+            zoom_timer: 0.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn snap_to(&mut self, x: f64, y: f64) {
+        self.x = x;
+        self.y = y;
+        self.clamp_to_world();
+    }
+
+    pub fn world_to_screen(&self, x: f64, y: f64) -> (f64, f64) {
+        let view_x = self.x + self.shake_x + self.kick_x;
+        let view_y = self.y + self.shake_y + self.kick_y;
+
+        ((x - view_x) * self.zoom() + crate::SCREEN_WIDTH as f64 / 2.0, (y - view_y) * self.zoom() + crate::SCREEN_HEIGHT as f64 / 2.0)
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom * self.fit_zoom
+    }
+
+    pub fn add_trauma(&mut self, amount: f64) {
+        if self.effects_enabled {
+            self.trauma = (self.trauma + amount).min(1.0);
+        }
+    }
+
+    //Jolts the view towards a direction, it springs back on its own
+    pub fn kick(&mut self, dir_x: f64, dir_y: f64, strength: f64) {
+        if !self.effects_enabled {
+            return;
+        }
+        let length = (dir_x * dir_x + dir_y * dir_y).sqrt();
+        if length > f64::EPSILON {
+            self.kick_x += dir_x / length * strength;
+            self.kick_y += dir_y / length * strength;
+        }
+    }
+
+    //Zooms to peak and eases back to normal over duration seconds
+    pub fn zoom_pulse(&mut self, peak: f64, duration: f64) {
+        if self.effects_enabled && duration > 0.0 {
+            self.zoom_peak = peak;
+            self.zoom_duration = duration;
+            self.zoom_timer = duration;
+        }
+    }
+
+    fn update_effects(&mut self, delta_time: f64) {
+        if !self.effects_enabled {
+            self.trauma = 0.0;
+            self.shake_x = 0.0;
+            self.shake_y = 0.0;
+            self.kick_x = 0.0;
+            self.kick_y = 0.0;
+            self.zoom = 1.0;
+            return;
+        }
+
+        self.trauma = (self.trauma - TRAUMA_DECAY * delta_time).max(0.0);
+        let shake = MAX_SHAKE_OFFSET * self.trauma * self.trauma;
+        let mut rng = rand::thread_rng();
+        self.shake_x = shake * rng.gen_range(-1.0..=1.0);
+        self.shake_y = shake * rng.gen_range(-1.0..=1.0);
+
+        let recovery = (-KICK_RECOVERY * delta_time).exp();
+        self.kick_x *= recovery;
+        self.kick_y *= recovery;
+
+        self.zoom_timer = (self.zoom_timer - delta_time).max(0.0);
+        self.zoom = if self.zoom_duration > 0.0 {
+            1.0 + (self.zoom_peak - 1.0) * (self.zoom_timer / self.zoom_duration)
+        } else {
+            1.0
+        };
+    }
+
+    //Never show anything outside the world
+    fn clamp_to_world(&mut self) {
+        let half_screen_width = crate::SCREEN_WIDTH as f64 / 2.0 / self.fit_zoom;
+        let half_screen_height = crate::SCREEN_HEIGHT as f64 / 2.0 / self.fit_zoom;
+
+        self.x = self.x.clamp(half_screen_width, (crate::WORLD_WIDTH as f64 - half_screen_width).max(half_screen_width));
+        self.y = self.y.clamp(half_screen_height, (crate::WORLD_HEIGHT as f64 - half_screen_height).max(half_screen_height));
+    }
+}
+
+pub struct CameraFollow;
+
+impl<'a> System<'a> for CameraFollow {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::Player>,
+        Write<'a, Camera>,
+        Read<'a, crate::DeltaTime>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, players, mut camera, delta_time) = data;
+        camera.update_effects(delta_time.0);
+
+        //Follow the midpoint of every ship so co-op players share the view
+        let (mut sum_x, mut sum_y, mut count) = (0.0, 0.0, 0);
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for (pos, _) in (&positions, &players).join() {
+            sum_x += pos.x;
+            sum_y += pos.y;
+            count += 1;
+            min_x = min_x.min(pos.x);
+            min_y = min_y.min(pos.y);
+            max_x = max_x.max(pos.x);
+            max_y = max_y.max(pos.y);
+        }
+        if count == 0 {
+            return;
+        }
+        let (target_x, target_y) = (sum_x / count as f64, sum_y / count as f64);
+
+        //Zoom out when the ships spread further apart than one screen, at most far enough to show the whole world
+        let min_zoom = (crate::SCREEN_WIDTH as f64 / crate::WORLD_WIDTH as f64).max(crate::SCREEN_HEIGHT as f64 / crate::WORLD_HEIGHT as f64);
+        let fit_width = crate::SCREEN_WIDTH as f64 / (max_x - min_x + 2.0 * FIT_MARGIN);
+        let fit_height = crate::SCREEN_HEIGHT as f64 / (max_y - min_y + 2.0 * FIT_MARGIN);
+        let desired_zoom = fit_width.min(fit_height).clamp(min_zoom, 1.0);
+        let zoom_blend = 1.0 - (-FIT_SMOOTHING * delta_time.0).exp();
+        camera.fit_zoom += (desired_zoom - camera.fit_zoom) * zoom_blend;
+
+        //Only chase the part of the offset that leaves the dead zone
+        let desired_x = camera.x + dead_zone_offset(target_x - camera.x, camera.dead_zone_width);
+        let desired_y = camera.y + dead_zone_offset(target_y - camera.y, camera.dead_zone_height);
+
+        let blend = 1.0 - (-camera.smoothing * delta_time.0).exp();
+        camera.x += (desired_x - camera.x) * blend;
+        camera.y += (desired_y - camera.y) * blend;
+        camera.clamp_to_world();
+    }
+}
+
+fn dead_zone_offset(offset: f64, half_size: f64) -> f64 {
+    if offset > half_size {
+        offset - half_size
+    } else if offset < -half_size {
+        offset + half_size
+    } else {
+        0.0
+    }
+}
+
+//Fixed points scattered over the world so movement is visible when nothing else is on screen
+pub struct Starfield {
+    pub stars: Vec<(f64, f64)>,
+}
+
+impl Starfield {
+    pub fn new(count: usize) -> Self {
+        let mut rng = rand::thread_rng();
+        let stars = (0..count)
+            .map(|_| (rng.gen_range(0.0..crate::WORLD_WIDTH as f64), rng.gen_range(0.0..crate::WORLD_HEIGHT as f64)))
+            .collect();
+        Starfield { stars }
+    }
+}
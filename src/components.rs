@@ -1,8 +1,10 @@
 use specs::{prelude::*};
 use specs_derive::Component;
-use vector2d::Vector2D;
 
-#[derive(PartialEq)]
+use crate::tuning::{Difficulty, GameMode};
+use crate::shop::Upgrades;
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Quadrant {
     TopLeft,
     TopRight,
@@ -10,6 +12,15 @@ pub enum Quadrant {
     BottomRight,
 }
 
+//What happens to an entity when it reaches the edge of the playfield
+#[derive(Component, Clone, Copy, PartialEq)]
+pub enum BoundaryBehavior {
+    Wrap,
+    Bounce,
+    Despawn,
+    Clamp,
+}
+
 #[derive(Component)]
 pub struct Renderable {
     pub texture_name: String,
@@ -17,7 +28,8 @@ pub struct Renderable {
     pub img_height: u32,
     pub output_width: u32,
     pub output_height: u32,
-    pub img_rotation: f64
+    pub img_rotation: f64,
+    pub visible: bool
 }
 
 #[derive(Component)]
@@ -27,32 +39,287 @@ pub struct Position {
     pub rot: f64
 }
 
+#[derive(Component)]
+pub struct Velocity {
+    pub x: f64,
+    pub y: f64
+}
+
+//Degrees per second applied to Position::rot
+#[derive(Component)]
+pub struct AngularVelocity {
+    pub speed: f64
+}
+
+//Accumulated for one frame and cleared by the movement system once integrated
+#[derive(Component, Default)]
+pub struct Acceleration {
+    pub x: f64,
+    pub y: f64
+}
+
+//Exponential damping rate per second, velocity decays by e^(-damping * t)
+#[derive(Component)]
+pub struct Drag {
+    pub damping: f64
+}
+
+impl Drag {
+    //Damping equivalent to multiplying velocity by factor once per frame at the reference frame rate
+    pub fn from_frame_factor(factor: f64) -> Self {
+        Drag { damping: -factor.ln() * crate::movement::REFERENCE_FRAME_RATE }
+    }
+}
+
+#[derive(Component)]
+pub struct SpeedLimit {
+    pub max_speed: f64
+}
+
+//Seconds left before the entity is deleted
+#[derive(Component)]
+pub struct Lifetime {
+    pub remaining: f64
+}
+
 #[derive(Component)]
 pub struct Player {
-    pub impulse: Vector2D<f64>,
-    pub current_speed: Vector2D<f64>,
     pub rotation_speed: f64,
-    pub max_speed: f64,
-    pub friction: f64,
-    pub health: i32
+    //Acceleration in units per second squared while thrusting
+    pub thrust: f64,
+    pub health: i32,
+    //Health repairs and wave heals cannot go past, the difficulty's maximum plus any upgrades
+    pub max_health: i32,
+    //Seconds left of the grace period after a hit during which the ship cannot be hurt
+    pub invulnerable: f64,
+    //Seconds until the hyperspace jump can be used again
+    pub hyperspace_cooldown: f64,
+    //Homing missiles left, topped up every wave
+    pub missiles: u32,
+    //Which player this is, picks the bindings and where score is credited
+    pub slot: usize
+}
+
+//Armoured ones take several hits, explosive ones blow up when destroyed and magnetic ones drift towards the nearest ship
+#[derive(Clone, Copy, PartialEq)]
+pub enum AsteroidVariant {
+    Plain,
+    Armoured,
+    Explosive,
+    Magnetic,
 }
 
 #[derive(Component)]
 pub struct Asteroid {
-    pub rotation_speed: f64,
-    pub speed: f64,
-    pub friction: f64,
     pub quadrant: Quadrant,
+    pub size: u32,
+    pub variant: AsteroidVariant,
+    pub health: i32,
+}
+
+//Which side fired a projectile, lasers never hurt their own side
+#[derive(Clone, Copy, PartialEq)]
+pub enum Faction {
+    Player,
+    Enemy,
 }
 
 #[derive(Component)]
 pub struct Laser {
-    pub speed: f64,
+    //How many more asteroids it can pass through before it is used up
+    pub pierce: u32,
+    pub faction: Faction,
+    //Set once it has hit something, for accuracy tracking
+    pub hit: bool,
+    //Slot of the player that fired it, score for what it destroys goes to them
+    pub owner: Option<usize>
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum WeaponKind {
+    Single,
+    Spread,
+    Rapid,
+    PiercingBeam,
+}
+
+//Everything needed to spawn one projectile
+#[derive(Clone)]
+pub struct ProjectilePrefab {
+    pub texture_name: &'static str,
+    pub img_width: u32,
+    pub img_height: u32,
+    pub output_width: u32,
+    pub output_height: u32,
+    //None uses the laser lifetime from Tuning
+    pub lifetime: Option<f64>,
+    pub pierce: u32
+}
+
+#[derive(Clone)]
+pub struct WeaponStats {
+    pub kind: WeaponKind,
+    pub name: &'static str,
+    //Bursts per second
+    pub fire_rate: f64,
+    pub projectile: ProjectilePrefab,
+    pub projectile_speed: f64,
+    pub projectiles_per_shot: u32,
+    //Degrees between neighbouring projectiles of one shot
+    pub spread: f64,
+    pub burst_count: u32,
+    pub burst_interval: f64
+}
+
+#[derive(Component)]
+pub struct Weapon {
+    pub loadout: Vec<WeaponStats>,
+    pub selected: usize,
+    //Set by the owner to ask for a shot, cleared by the weapon system once handled
+    pub trigger: bool,
+    //Direction to fire in degrees, None fires where the owner is facing
+    pub aim: Option<f64>,
+    pub cooldown: f64,
+    pub burst_remaining: u32,
+    pub burst_timer: f64
 }
 
 #[derive(Component)]
 pub struct GameData {
     pub score: u32,
     pub level: u32,
-    pub invincible_player: bool
+    pub invincible_player: bool,
+    //Spare ships shared by every player, the run ends when a ship is lost with none to spare
+    pub lives: u32,
+    //Per player slot, counts down while that player waits to respawn
+    pub respawn_timers: Vec<Option<f64>>,
+    //Per player slot, score is the team total of these
+    pub player_scores: Vec<u32>,
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+    //Versus only, per player slot
+    pub kills: Vec<u32>,
+    pub round_wins: Vec<u32>,
+    //Counts down the pause after a versus round is decided
+    pub round_timer: Option<f64>,
+    //Per player slot, earned along with score and spent in the shop between waves
+    pub credits: Vec<u32>,
+    pub upgrades: Vec<Upgrades>,
+    //Seconds of play so far, the clock for time attack and the score in survival
+    pub elapsed: f64
+}
+
+impl GameData {
+    //Adds points scaled by the difficulty's score multiplier, crediting the player who earned them.
+    //Survival only scores time survived
+    pub fn add_score(&mut self, owner: Option<usize>, points: u32) {
+        if self.mode == GameMode::Survival {
+            return;
+        }
+        let points = (points as f64 * self.difficulty.score_multiplier()).round() as u32;
+        self.score += points;
+        if let Some(player_score) = owner.and_then(|slot| self.player_scores.get_mut(slot)) {
+            *player_score += points;
+        }
+        if let Some(credits) = owner.and_then(|slot| self.credits.get_mut(slot)) {
+            *credits += points;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PickupKind {
+    Repair,
+    Shield,
+    RapidFire,
+    SpreadShot,
+    ScoreBonus,
+}
+
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: PickupKind
+}
+
+//Seconds left on each timed pickup effect, zero when inactive
+#[derive(Component, Default)]
+pub struct PowerUps {
+    pub shield: f64,
+    pub rapid_fire: f64,
+    pub spread_shot: f64
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EnemyState {
+    //Closing in on the player
+    Seek,
+    //Circling the player at a distance while shooting
+    Orbit,
+    //Badly damaged and running away
+    Flee,
+}
+
+#[derive(Component)]
+pub struct Enemy {
+    pub health: i32,
+    pub max_health: i32,
+    pub score_value: u32,
+    pub state: EnemyState,
+    pub max_speed: f64,
+    pub steering_force: f64,
+    //Whether it circles clockwise or counter clockwise
+    pub orbit_direction: f64
+}
+
+#[derive(Component)]
+pub struct Boss {
+    //1 while the outer weak points are intact, 2 once half of them are gone, 3 once the core is exposed
+    pub phase: u32,
+    //Remaining health of the core and all weak points together, shown on the boss bar
+    pub health: i32,
+    pub max_health: i32,
+    pub core_health: i32,
+    pub score_value: u32,
+    pub attack_timer: f64,
+    //Rotating angle used by the spiral attack
+    pub spiral_angle: f64,
+    //Player slot that last damaged the core, they get the score for the kill
+    pub last_hit_by: Option<usize>
+}
+
+//A piece attached to a boss, weak points take damage while the rest is armour that blocks shots
+#[derive(Component)]
+pub struct BossPart {
+    pub boss: Entity,
+    pub offset_x: f64,
+    pub offset_y: f64,
+    pub weak_point: bool,
+    pub health: i32
+}
+
+//Secondary weapon that steers itself into the nearest asteroid, enemy or boss weak point ahead of it
+#[derive(Component)]
+pub struct Missile {
+    //What it is homing in on, a new one is picked whenever this is lost
+    pub target: Option<Entity>,
+    pub owner: Option<usize>
+}
+
+//Area damage dealt once to every asteroid and enemy within the radius, the entity then stays as the blast effect
+#[derive(Component)]
+pub struct Explosion {
+    pub radius: f64,
+    pub damage: i32,
+    pub owner: Option<usize>,
+    //Missile blasts spare the ships, exploding asteroids do not
+    pub hurts_players: bool,
+    pub detonated: bool
+}
+
+//Pulls players, asteroids and lasers in with an inverse square force and swallows anything that reaches the core
+#[derive(Component, Clone)]
+pub struct GravityWell {
+    pub strength: f64,
+    pub core_radius: f64,
+    pub range: f64
 }
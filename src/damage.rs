@@ -0,0 +1,70 @@
+use specs::prelude::*;
+use specs::Join;
+
+use crate::components;
+use crate::camera::Camera;
+use crate::tuning::Tuning;
+use crate::achievements::{GameEvent, GameEvents};
+
+//The player being hit and what it takes to react to the hit
+pub struct PlayerTarget<'a> {
+    pub player: &'a mut components::Player,
+    pub entity: Entity,
+    pub pos: &'a components::Position,
+    pub velocity: Option<&'a mut components::Velocity>,
+    pub shielded: bool,
+}
+
+//Applies one hit to a player from something at from, deleting the ship when it runs out of health.
+//Shielded players shrug the hit off, and after a hit the ship cannot be hurt again for a short while
+//so overlapping hazards cannot drain several health in one go. Returns true if the hit landed
+pub fn damage_player(target: PlayerTarget, from: (f64, f64), entities: &Entities, camera: &mut Camera, tuning: &Tuning, events: &mut GameEvents) -> bool {
+    let player = target.player;
+    if target.shielded || player.invulnerable > 0.0 {
+        return false;
+    }
+    player.health -= 1;
+    player.invulnerable = tuning.invulnerability_duration;
+    events.push(GameEvent::PlayerDamaged);
+
+    //Throw the ship away from the impact
+    let away_x = target.pos.x - from.0;
+    let away_y = target.pos.y - from.1;
+    let distance = (away_x * away_x + away_y * away_y).sqrt();
+    if let Some(velocity) = target.velocity {
+        let (dir_x, dir_y) = if distance > f64::EPSILON { (away_x / distance, away_y / distance) } else { (0.0, -1.0) };
+        velocity.x = dir_x * tuning.knockback_speed;
+        velocity.y = dir_y * tuning.knockback_speed;
+    }
+
+    camera.add_trauma(0.6);
+    camera.kick(away_x, away_y, 25.0);
+    camera.zoom_pulse(1.08, 0.3);
+
+    if player.health < 1 {
+        entities.delete(target.entity).ok();
+    }
+    true
+}
+
+//Counts down the post hit grace period and blinks the ship while it lasts
+pub struct Invulnerability;
+
+impl<'a> System<'a> for Invulnerability {
+    type SystemData = (
+        WriteStorage<'a, components::Player>,
+        WriteStorage<'a, components::Renderable>,
+        ReadExpect<'a, Tuning>,
+        Read<'a, crate::DeltaTime>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (mut players, mut renderables, tuning, delta_time) = data;
+
+        for (player, renderable) in (&mut players, &mut renderables).join() {
+            player.invulnerable = (player.invulnerable - delta_time.0).max(0.0);
+
+            let blink_step = (player.invulnerable / tuning.invulnerability_blink_interval.max(0.01)) as u64;
+            renderable.visible = player.invulnerable <= 0.0 || blink_step & 1 == 0;
+        }
+    }
+}
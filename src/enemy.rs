@@ -0,0 +1,142 @@
+use rand::Rng;
+use specs::prelude::*;
+use specs::{Entities, Join};
+
+use crate::components;
+use crate::components::{Enemy, EnemyState};
+use crate::weapon;
+
+//First level UFOs show up on
+pub const ENEMY_START_LEVEL: u32 = 3;
+
+const UFO_SIZE: u32 = 90;
+const ORBIT_RADIUS: f64 = 350.0;
+const FIRE_RANGE: f64 = 700.0;
+//Degrees of random error added to every aimed shot
+const AIM_ERROR: f64 = 8.0;
+//Below this fraction of health the UFO gives up and flees
+const FLEE_HEALTH_FRACTION: f64 = 0.35;
+
+pub fn enemies_for_level(level: u32) -> u32 {
+    if level < ENEMY_START_LEVEL {
+        0
+    } else {
+        (level - ENEMY_START_LEVEL) / 2 + 1
+    }
+}
+
+//Aggression scales how fast the UFO moves, turns and shoots
+pub fn build_ufo<B: Builder>(builder: B, x: f64, y: f64, level: u32, aggression: f64) -> B {
+    let mut rng = rand::thread_rng();
+    let health = 3 + (level / 4) as i32;
+    let mut loadout = weapon::enemy_loadout();
+    for stats in &mut loadout {
+        stats.fire_rate *= aggression;
+    }
+
+    builder
+        .with(components::Position { x, y, rot: 0.0 })
+        .with(components::Renderable {
+            texture_name: String::from("Assets/Images/ufo.png"),
+            img_width: 128,
+            img_height: 128,
+            output_width: UFO_SIZE,
+            output_height: UFO_SIZE,
+            img_rotation: 0.0,
+            visible: true
+        })
+        .with(Enemy {
+            health,
+            max_health: health,
+            score_value: 200,
+            state: EnemyState::Seek,
+            max_speed: 220.0 * aggression,
+            steering_force: 400.0 * aggression,
+            orbit_direction: if rng.gen_bool(0.5) { 1.0 } else { -1.0 }
+        })
+        .with(components::Velocity { x: 0.0, y: 0.0 })
+        .with(components::Acceleration::default())
+        .with(components::Drag { damping: 0.5 })
+        .with(components::SpeedLimit { max_speed: 260.0 * aggression })
+        .with(components::BoundaryBehavior::Clamp)
+        .with(components::Weapon::new(loadout))
+}
+
+pub struct EnemyAi;
+
+impl<'a> System<'a> for EnemyAi {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::Velocity>,
+        WriteStorage<'a, components::Acceleration>,
+        WriteStorage<'a, Enemy>,
+        WriteStorage<'a, components::Weapon>,
+        ReadStorage<'a, components::Player>,
+        WriteStorage<'a, components::BoundaryBehavior>,
+        Entities<'a>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, velocities, mut accelerations, mut enemies, mut weapons, players, mut boundaries, entities) = data;
+
+        let player_positions: Vec<(f64, f64)> = (&positions, &players).join().map(|(pos, _)| (pos.x, pos.y)).collect();
+        let mut rng = rand::thread_rng();
+
+        for (pos, velocity, acceleration, enemy, weapon, entity) in (&positions, &velocities, &mut accelerations, &mut enemies, &mut weapons, &entities).join() {
+            //Hunt whichever player is closest
+            let target = player_positions.iter()
+                .map(|(x, y)| (x - pos.x, y - pos.y))
+                .min_by(|a, b| (a.0 * a.0 + a.1 * a.1).total_cmp(&(b.0 * b.0 + b.1 * b.1)));
+            let (to_x, to_y) = match target {
+                Some(offset) => offset,
+                None => {
+                    acceleration.x = 0.0;
+                    acceleration.y = 0.0;
+                    continue;
+                }
+            };
+            let distance = (to_x * to_x + to_y * to_y).sqrt().max(1.0);
+            let (dir_x, dir_y) = (to_x / distance, to_y / distance);
+
+            if (enemy.health as f64) < enemy.max_health as f64 * FLEE_HEALTH_FRACTION {
+                //A fleeing UFO escapes off the edge of the world instead of hiding there and holding up the wave
+                if enemy.state != EnemyState::Flee {
+                    boundaries.insert(entity, components::BoundaryBehavior::Despawn).ok();
+                }
+                enemy.state = EnemyState::Flee;
+            } else if distance > ORBIT_RADIUS * 1.3 {
+                enemy.state = EnemyState::Seek;
+            } else if distance < ORBIT_RADIUS {
+                enemy.state = EnemyState::Orbit;
+            }
+
+            let (desired_x, desired_y) = match enemy.state {
+                EnemyState::Seek => (dir_x * enemy.max_speed, dir_y * enemy.max_speed),
+                EnemyState::Orbit => {
+                    //Move along the tangent, pulled in or out to hold the orbit radius
+                    let tangent_x = -dir_y * enemy.orbit_direction;
+                    let tangent_y = dir_x * enemy.orbit_direction;
+                    let radial = ((distance - ORBIT_RADIUS) / ORBIT_RADIUS).clamp(-1.0, 1.0);
+                    ((tangent_x + dir_x * radial) * enemy.max_speed, (tangent_y + dir_y * radial) * enemy.max_speed)
+                },
+                EnemyState::Flee => (-dir_x * enemy.max_speed, -dir_y * enemy.max_speed),
+            };
+
+            //Steering is the change of velocity needed, capped so turns stay smooth
+            let mut steer_x = desired_x - velocity.x;
+            let mut steer_y = desired_y - velocity.y;
+            let steer_length = (steer_x * steer_x + steer_y * steer_y).sqrt();
+            if steer_length > enemy.steering_force {
+                steer_x = steer_x / steer_length * enemy.steering_force;
+                steer_y = steer_y / steer_length * enemy.steering_force;
+            }
+            acceleration.x += steer_x;
+            acceleration.y += steer_y;
+
+            //Aim at the player and shoot when close enough, a fleeing ship only runs
+            weapon.aim = Some(crate::movement::rotation_of(to_x, to_y) + rng.gen_range(-AIM_ERROR..AIM_ERROR));
+            if enemy.state != EnemyState::Flee && distance < FIRE_RANGE {
+                weapon.trigger = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+use specs::prelude::*;
+use specs::{Entities, Join};
+
+use crate::{asteroid, boss, components, damage, pickup};
+use crate::camera::Camera;
+use crate::achievements::{GameEvent, GameEvents};
+use crate::tuning::Tuning;
+
+//How long the blast stays on screen after dealing its damage
+const EXPLOSION_DURATION: f64 = 0.35;
+
+pub fn build_explosion<B: Builder>(builder: B, x: f64, y: f64, radius: f64, damage: i32, owner: Option<usize>, hurts_players: bool) -> B {
+    let size = (radius * 2.0) as u32;
+    builder
+        .with(components::Position { x, y, rot: rand::thread_rng().gen_range(0.0..360.0) })
+        .with(components::Renderable {
+            texture_name: String::from("Assets/Images/explosion.png"),
+            img_width: 128,
+            img_height: 128,
+            output_width: size,
+            output_height: size,
+            img_rotation: 0.0,
+            visible: true
+        })
+        .with(components::Explosion { radius, damage, owner, hurts_players, detonated: false })
+        .with(components::Lifetime { remaining: EXPLOSION_DURATION })
+}
+
+//Applies the damage of new explosions to everything caught in the blast.
+//Explosive asteroids destroyed here leave explosions of their own, which go off the next frame.
+//Bosses take blasts by the same rules as shots, BossDamage removes a boss whose core is destroyed
+pub struct ExplosionDamage;
+
+impl<'a> System<'a> for ExplosionDamage {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        WriteStorage<'a, components::Renderable>,
+        WriteStorage<'a, components::Velocity>,
+        WriteStorage<'a, components::Asteroid>,
+        WriteStorage<'a, components::Enemy>,
+        WriteStorage<'a, components::Boss>,
+        WriteStorage<'a, components::BossPart>,
+        WriteStorage<'a, components::Player>,
+        ReadStorage<'a, components::PowerUps>,
+        WriteStorage<'a, components::Explosion>,
+        WriteStorage<'a, components::GameData>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        ReadExpect<'a, Tuning>,
+        Write<'a, Camera>,
+        Write<'a, GameEvents>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, mut renderables, mut velocities, mut asteroids, mut enemies, mut bosses, mut parts, mut players, power_ups, mut explosions, mut gamedatas, entities, lazy, tuning, mut camera, mut events) = data;
+
+        //Overlapping blasts in the same frame must not destroy the same thing twice
+        let mut destroyed = HashSet::new();
+        let mut destroyed_sizes = Vec::new();
+        let mut enemy_scores = Vec::new();
+        let invincible_player = (&gamedatas).join().any(|data| data.invincible_player);
+
+        for (explosion_pos, explosion) in (&positions, &mut explosions).join() {
+            if explosion.detonated {
+                continue;
+            }
+            explosion.detonated = true;
+            camera.add_trauma(0.25);
+
+            for (asteroid_pos, asteroid_renderable, asteroid_velocity, asteroid, asteroid_entity) in (&positions, &mut renderables, &velocities, &mut asteroids, &entities).join() {
+                if destroyed.contains(&asteroid_entity) {
+                    continue;
+                }
+                if !within(explosion_pos, asteroid_pos, explosion.radius + asteroid_renderable.output_width as f64 / 2.0) {
+                    continue;
+                }
+                if !asteroid::damage_asteroid(asteroid, asteroid_renderable, explosion.damage) {
+                    continue;
+                }
+                entities.delete(asteroid_entity).ok();
+                destroyed.insert(asteroid_entity);
+                destroyed_sizes.push((asteroid.size, explosion.owner));
+                events.push(GameEvent::AsteroidDestroyed);
+                asteroid::break_asteroid(&lazy, &entities, asteroid_pos, asteroid_velocity, asteroid, explosion.owner, &tuning);
+            }
+
+            for (enemy_pos, enemy_renderable, enemy, enemy_entity) in (&positions, &renderables, &mut enemies, &entities).join() {
+                if destroyed.contains(&enemy_entity) {
+                    continue;
+                }
+                if !within(explosion_pos, enemy_pos, explosion.radius + enemy_renderable.output_width as f64 / 2.0) {
+                    continue;
+                }
+                enemy.health -= explosion.damage;
+                if enemy.health < 1 {
+                    entities.delete(enemy_entity).ok();
+                    destroyed.insert(enemy_entity);
+                    events.push(GameEvent::EnemyDestroyed);
+                    enemy_scores.push((enemy.score_value, explosion.owner));
+                    pickup::maybe_drop_pickup(&lazy, &entities, enemy_pos.x, enemy_pos.y, &tuning);
+                }
+            }
+
+            for (part_pos, part_renderable, part, part_entity) in (&positions, &renderables, &mut parts, &entities).join() {
+                if !within(explosion_pos, part_pos, explosion.radius + part_renderable.output_width as f64 / 2.0) {
+                    continue;
+                }
+                if boss::damage_part(part, explosion.damage) {
+                    entities.delete(part_entity).ok();
+                    camera.add_trauma(0.3);
+                }
+            }
+
+            for (boss_pos, boss_renderable, boss) in (&positions, &renderables, &mut bosses).join() {
+                if within(explosion_pos, boss_pos, explosion.radius + boss_renderable.output_width as f64 / 2.0) {
+                    boss::damage_core(boss, explosion.damage, explosion.owner);
+                }
+            }
+
+            if !explosion.hurts_players || invincible_player {
+                continue;
+            }
+            for (player_pos, player_renderable, player, power_ups, velocity, player_entity) in (&positions, &renderables, &mut players, power_ups.maybe(), (&mut velocities).maybe(), &entities).join() {
+                if !within(explosion_pos, player_pos, explosion.radius + player_renderable.output_width as f64 / 2.0) {
+                    continue;
+                }
+                let shielded = power_ups.is_some_and(|power_ups| power_ups.shield > 0.0);
+                let target = damage::PlayerTarget { player, entity: player_entity, pos: player_pos, velocity, shielded };
+                damage::damage_player(target, (explosion_pos.x, explosion_pos.y), &entities, &mut camera, &tuning, &mut events);
+            }
+        }
+
+        if !destroyed_sizes.is_empty() || !enemy_scores.is_empty() {
+            for gamedata in (&mut gamedatas).join() {
+                for (size, owner) in &destroyed_sizes {
+                    gamedata.add_score(*owner, asteroid::score_for_size(*size, gamedata.level));
+                }
+                for (score_value, owner) in &enemy_scores {
+                    gamedata.add_score(*owner, score_value * gamedata.level);
+                }
+            }
+        }
+    }
+}
+
+fn within(a: &components::Position, b: &components::Position, radius: f64) -> bool {
+    let diff_x = a.x - b.x;
+    let diff_y = a.y - b.y;
+    diff_x * diff_x + diff_y * diff_y < radius * radius
+}
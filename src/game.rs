@@ -1,101 +1,219 @@
 use std::collections::HashMap;
-use specs::{World, WorldExt, Builder, Join};
-use vector2d::Vector2D;
+use specs::{World, WorldExt, Builder, Entity, Join};
 use rand::Rng;
 
 use crate::{components};
 use crate::components::GameData;
-use crate::components::Quadrant;
+use crate::components::{AsteroidVariant, Quadrant};
 use crate::input_manager;
+use crate::input_manager::{InputAction, PlayerBindings};
+use crate::asteroid;
+use crate::movement;
+use crate::enemy;
+use crate::boss;
+use crate::pickup;
+use crate::missile;
+use crate::gravity;
+use crate::shop::{Upgrade, Upgrades, ALL_UPGRADES};
+use crate::waves::{Waves, WaveDefinition, WaveSpawn, WaveSpawner};
+use crate::tuning::{Difficulty, Tuning, FireMode};
+use crate::camera::Camera;
+use crate::highscores::{self, HighScoreEntry, HighScores};
+use crate::tuning::{GameMode, ALL_DIFFICULTIES, ALL_GAME_MODES};
+use crate::achievements::{self, Achievements, GameEvent, GameEvents};
+
+//Number keys select the weapon in the matching loadout slot
+const WEAPON_KEYS: [&str; 4] = ["1", "2", "3", "4"];
+//Respawn attempts before settling for the emptiest spot found
+const RESPAWN_ATTEMPTS: u32 = 50;
+//How long the flash left at both ends of a hyperspace jump lasts
+const HYPERSPACE_EFFECT_DURATION: f64 = 0.4;
+//Distance between the ships at the start of a co-op run and of a versus round
+const COOP_START_SPACING: f64 = 120.0;
+const VERSUS_START_SPACING: f64 = 900.0;
+//Asteroids scattered around the versus arena for cover
+const VERSUS_ASTEROIDS: u32 = 8;
+//Pause after a versus round is decided before the next one starts
+const VERSUS_ROUND_END_DELAY: f64 = 3.0;
+//Points for destroying the other ship in versus
+const VERSUS_KILL_SCORE: u32 = 1000;
+//Ship handling before any upgrades
+const PLAYER_ROTATION_SPEED: f64 = 200.0;
+const PLAYER_MAX_SPEED: f64 = 200.0;
+//Survival gets harder every this many seconds and spawns come in faster by this many seconds for each second survived
+const SURVIVAL_LEVEL_DURATION: f64 = 30.0;
+const SURVIVAL_SPAWN_RAMP: f64 = 0.01;
+const PLAYER_TEXTURES: [&str; 2] = ["Assets/Images/rocket.png", "Assets/Images/rocket_2.png"];
+const ALL_QUADRANTS: [Quadrant; 4] = [Quadrant::TopLeft, Quadrant::TopRight, Quadrant::BottomLeft, Quadrant::BottomRight];
+//Which part of the game is showing
+#[derive(Default)]
+pub enum Screen {
+    #[default]
+    Title,
+    Playing,
+    //The run is over and made the high score table, waiting for the player's name
+    NameEntry(HighScoreEntry),
+    //Between waves each player in turn spends credits on upgrades, selected is the highlighted one
+    Shop { slot: usize, selected: usize },
+}
 
-const PLAYER_MAX_HEALTH: i32 = 10;
 pub fn update(ecs: &mut World, input_manager: &mut HashMap<String, bool>, delta_time: f64) {
-    reload_world_if_no_players(ecs);
-
-    let mut current_player_pos = components::Position { x: 0.0, y: 0.0, rot: 0.0 };
-    {
-        let players = ecs.read_storage::<components::Player>();
-        let positions = ecs.read_storage::<components::Position>();
+    if matches!(*ecs.read_resource::<Screen>(), Screen::Title) {
+        update_title(ecs, input_manager);
+        return;
+    }
+    if matches!(*ecs.read_resource::<Screen>(), Screen::NameEntry(_)) {
+        update_name_entry(ecs, input_manager);
+        return;
+    }
+    if matches!(*ecs.read_resource::<Screen>(), Screen::Shop { .. }) {
+        update_shop(ecs, input_manager);
+        return;
+    }
+    let mode = current_mode(ecs);
+    match mode {
+        GameMode::Versus => update_versus_round(ecs, delta_time),
+        _ => handle_player_death(ecs, delta_time),
+    }
+    //Losing the last life or running out of time leaves the world empty
+    if matches!(*ecs.read_resource::<Screen>(), Screen::Playing) {
+        update_run_clock(ecs, mode, delta_time);
+    }
+    if !matches!(*ecs.read_resource::<Screen>(), Screen::Playing) {
+        return;
+    }
 
-        for (pos, _) in (&positions, &players).join() {
-            current_player_pos.x = pos.x;
-            current_player_pos.y = pos.y;
-        }
+    let player_positions = player_positions(ecs);
+    //Survival trickles asteroids in instead, and the versus arena has no waves
+    if mode == GameMode::Survival {
+        queue_survival_spawn(ecs);
+        release_wave_spawns(ecs, &player_positions, delta_time);
     }
-    {
+    if mode.has_waves() {
         let asteroid_count;
         {
             let asteroids = ecs.read_storage::<crate::components::Asteroid>();
-            asteroid_count = asteroids.join().count();
+            let enemies = ecs.read_storage::<crate::components::Enemy>();
+            let bosses = ecs.read_storage::<crate::components::Boss>();
+            //The wave is cleared once every asteroid, enemy and boss is gone
+            asteroid_count = asteroids.join().count() + enemies.join().count() + bosses.join().count();
         }
-        if asteroid_count < 1 {
-            spawn_asteroids(ecs, &current_player_pos, false);
+        if asteroid_count < 1 && ecs.read_resource::<WaveSpawner>().queue.is_empty() {
+            let level = {
+                let game_data = ecs.read_storage::<GameData>();
+                (&game_data).join().map(|data| data.level).next().unwrap_or(1)
+            };
+            ecs.write_resource::<GameEvents>().push(GameEvent::WaveCleared(level));
+            for player in (&mut ecs.write_storage::<components::Player>()).join() {
+                player.missiles = missile::MISSILE_AMMO;
+            }
+            //Black holes only last for the wave they came with
+            {
+                let entities = ecs.entities();
+                let wells = ecs.read_storage::<components::GravityWell>();
+                for (entity, _) in (&entities, &wells).join() {
+                    entities.delete(entity).ok();
+                }
+            }
+            //The next wave starts once everyone is done shopping
+            *ecs.write_resource::<Screen>() = Screen::Shop { slot: 0, selected: 0 };
+            return;
         }
+        release_wave_spawns(ecs, &player_positions, delta_time);
     }
 
-    let mut player_pos = components::Position { x: 0.0, y: 0.0, rot: 0.0 };
-    let mut should_fire_laser = false;
+    //toggle between tap and hold to fire
+    if input_manager::is_key_pressed(input_manager, "F") {
+        input_manager::key_up(input_manager, "F".to_string());
+        let mut tuning = ecs.write_resource::<Tuning>();
+        tuning.fire_mode = match tuning.fire_mode {
+            FireMode::Tap => FireMode::Hold,
+            FireMode::Hold => FireMode::Tap,
+        };
+    }
+    let fire_mode = ecs.read_resource::<Tuning>().fire_mode;
 
+    let mut hyperspace_jumps = Vec::new();
+    let mut missile_launches = Vec::new();
     {
-        let mut positions = ecs.write_storage::<components::Position>();
-        let mut player = ecs.write_storage::<components::Player>();
-        let mut renderables = ecs.write_storage::<components::Renderable>();
-
-        for (player, pos, renderable) in (&mut player, &mut positions, &mut renderables).join() {
-            if input_manager::is_key_pressed(&input_manager, "D") {
-                pos.rot += player.rotation_speed * delta_time;
+        let player_bindings = ecs.read_resource::<PlayerBindings>();
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<components::Position>();
+        let mut players = ecs.write_storage::<components::Player>();
+        let mut accelerations = ecs.write_storage::<components::Acceleration>();
+        let mut angular_velocities = ecs.write_storage::<components::AngularVelocity>();
+        let power_ups = ecs.read_storage::<components::PowerUps>();
+        let mut weapons = ecs.write_storage::<components::Weapon>();
+
+        for (player, pos, acceleration, angular_velocity, power_ups, weapon, entity) in (&mut players, &positions, &mut accelerations, &mut angular_velocities, &power_ups, &mut weapons, &entities).join() {
+            let Some(bindings) = player_bindings.slots.get(player.slot) else {
+                continue;
+            };
+            angular_velocity.speed = 0.0;
+            if input_manager::is_action_pressed(input_manager, bindings, InputAction::RotateRight) {
+                angular_velocity.speed += player.rotation_speed;
             }
-            if input_manager::is_key_pressed(&input_manager, "A") {
-                pos.rot -= player.rotation_speed * delta_time;
+            if input_manager::is_action_pressed(input_manager, bindings, InputAction::RotateLeft) {
+                angular_velocity.speed -= player.rotation_speed;
             }
 
-            update_movement(pos, player, delta_time);
-            if input_manager::is_key_pressed(&input_manager, "W") {
-                let radians = pos.rot.to_radians();
+            if input_manager::is_action_pressed(input_manager, bindings, InputAction::Thrust) {
+                let (dir_x, dir_y) = movement::heading(pos.rot);
 
-                let move_vec = Vector2D::<f64>::new(player.max_speed * radians.sin(), player.max_speed * radians.cos());
-
-                player.impulse += move_vec;
+                acceleration.x += player.thrust * dir_x;
+                acceleration.y += player.thrust * dir_y;
             }
 
-            //Keep the player withing 360 degrees
-            if pos.rot > 360.0 {
-                pos.rot -= 360.0;
+            //Weapon switching, the number keys belong to the first player
+            if player.slot == 0 {
+                for (slot, key) in WEAPON_KEYS.iter().enumerate() {
+                    if input_manager::is_key_pressed(input_manager, key) {
+                        weapon.select(slot);
+                    }
+                }
             }
-            if pos.rot < 360.0 {
-                pos.rot += 360.0;
+            if input_manager::is_action_pressed(input_manager, bindings, InputAction::NextWeapon) {
+                input_manager::release_action(input_manager, bindings, InputAction::NextWeapon);
+                let next = (weapon.selected + 1) % weapon.loadout.len();
+                weapon.select(next);
             }
 
-            //Screen wrapping
-            if pos.x > crate::SCREEN_WIDTH.into() {
-                pos.x -= crate::SCREEN_WIDTH as f64;
-            }
-            if pos.x < 0.0 {
-                pos.x += crate::SCREEN_WIDTH as f64;
-            }
-            if pos.y > crate::SCREEN_HEIGHT.into() {
-                pos.y -= crate::SCREEN_HEIGHT as f64;
+            //Shooting
+            if input_manager::is_action_pressed(input_manager, bindings, InputAction::Fire) {
+                //Holding keeps shooting in hold mode or with rapid fire, otherwise one shot per press
+                if fire_mode == FireMode::Tap && power_ups.rapid_fire <= 0.0 {
+                    input_manager::release_action(input_manager, bindings, InputAction::Fire);
+                }
+                weapon.trigger = true;
             }
-            if pos.y < 0.0 {
-                pos.y += crate::SCREEN_HEIGHT as f64;
+
+            //Hyperspace
+            player.hyperspace_cooldown = (player.hyperspace_cooldown - delta_time).max(0.0);
+            if input_manager::is_action_pressed(input_manager, bindings, InputAction::Hyperspace) {
+                input_manager::release_action(input_manager, bindings, InputAction::Hyperspace);
+                if player.hyperspace_cooldown <= 0.0 {
+                    hyperspace_jumps.push(entity);
+                }
             }
 
-            //Shooting
-            if input_manager::is_key_pressed(&input_manager, " ") {
-                input_manager::key_up(input_manager, " ".to_string());
-                should_fire_laser = true;
-                player_pos.x = pos.x;
-                player_pos.y = pos.y;
-                player_pos.rot = pos.rot;
+            //Homing missile
+            if input_manager::is_action_pressed(input_manager, bindings, InputAction::Missile) {
+                input_manager::release_action(input_manager, bindings, InputAction::Missile);
+                if player.missiles > 0 {
+                    player.missiles -= 1;
+                    missile_launches.push((components::Position { x: pos.x, y: pos.y, rot: pos.rot }, player.slot));
+                }
             }
-            renderable.img_rotation = pos.rot;
         }
     }
-    if should_fire_laser {
-        fire_laser(ecs, player_pos);
+    for entity in hyperspace_jumps {
+        hyperspace(ecs, entity);
     }
-    //toggle player invincibility
-    if input_manager::is_key_pressed(&input_manager, "i") {
+    for (origin, slot) in missile_launches {
+        missile::build_missile(ecs.create_entity(), &origin, Some(slot)).build();
+    }
+    //toggle player invincibility, zen keeps it on
+    if input_manager::is_key_pressed(input_manager, "i") && mode != GameMode::Zen {
         input_manager::key_up(input_manager, "i".to_string());
         let mut gamedata = ecs.write_storage::<GameData>();
         for data in (&mut gamedata).join() {
@@ -103,234 +221,432 @@ pub fn update(ecs: &mut World, input_manager: &mut HashMap<String, bool>, delta_
         }
     }
     //spawning 1000 asteroids
-    if input_manager::is_key_pressed(&input_manager, "o") {
+    if input_manager::is_key_pressed(input_manager, "o") {
         input_manager::key_up(input_manager, "o".to_string());
-        spawn_asteroids(ecs, &current_player_pos, true);
+        spawn_asteroids(ecs, &player_positions, true);
+    }
+}
+
+//Left and right pick the difficulty, up and down the number of players, M the mode, enter starts a run
+fn update_title(ecs: &mut World, input_manager: &mut HashMap<String, bool>) {
+    if input_manager::is_key_pressed(input_manager, "M") {
+        input_manager::key_up(input_manager, "M".to_string());
+        let mut tuning = ecs.write_resource::<Tuning>();
+        let current = ALL_GAME_MODES.iter().position(|mode| *mode == tuning.game_mode).unwrap_or(0);
+        tuning.game_mode = ALL_GAME_MODES[(current + 1) % ALL_GAME_MODES.len()];
+    }
+    for key in ["Up", "Down"] {
+        if input_manager::is_key_pressed(input_manager, key) {
+            input_manager::key_up(input_manager, key.to_string());
+            let mut tuning = ecs.write_resource::<Tuning>();
+            tuning.player_count = if tuning.player_count == 1 { 2 } else { 1 };
+        }
+    }
+    for (key, step) in [("Left", ALL_DIFFICULTIES.len() - 1), ("Right", 1)] {
+        if input_manager::is_key_pressed(input_manager, key) {
+            input_manager::key_up(input_manager, key.to_string());
+            let mut tuning = ecs.write_resource::<Tuning>();
+            let current = ALL_DIFFICULTIES.iter().position(|difficulty| *difficulty == tuning.difficulty).unwrap_or(0);
+            tuning.difficulty = ALL_DIFFICULTIES[(current + step) % ALL_DIFFICULTIES.len()];
+        }
+    }
+    if input_manager::is_key_pressed(input_manager, "Return") {
+        input_manager::key_up(input_manager, "Return".to_string());
+        ecs.delete_all();
+        load_world(ecs);
+        *ecs.write_resource::<Screen>() = Screen::Playing;
+    }
+}
+
+//Up and down pick an upgrade, enter buys it and C hands over to the next player or starts the next wave
+fn update_shop(ecs: &mut World, input_manager: &mut HashMap<String, bool>) {
+    let Screen::Shop { slot, selected } = *ecs.read_resource::<Screen>() else {
+        return;
+    };
+    for (key, step) in [("Up", ALL_UPGRADES.len() - 1), ("Down", 1)] {
+        if input_manager::is_key_pressed(input_manager, key) {
+            input_manager::key_up(input_manager, key.to_string());
+            *ecs.write_resource::<Screen>() = Screen::Shop { slot, selected: (selected + step) % ALL_UPGRADES.len() };
+        }
+    }
+    if input_manager::is_key_pressed(input_manager, "Return") {
+        input_manager::key_up(input_manager, "Return".to_string());
+        buy_upgrade(ecs, slot, ALL_UPGRADES[selected]);
+    }
+    if input_manager::is_key_pressed(input_manager, "C") {
+        input_manager::key_up(input_manager, "C".to_string());
+        let player_count = {
+            let game_data = ecs.read_storage::<GameData>();
+            (&game_data).join().map(|data| data.upgrades.len()).next().unwrap_or(1)
+        };
+        if slot + 1 < player_count {
+            *ecs.write_resource::<Screen>() = Screen::Shop { slot: slot + 1, selected: 0 };
+        } else {
+            *ecs.write_resource::<Screen>() = Screen::Playing;
+            let player_positions = player_positions(ecs);
+            spawn_asteroids(ecs, &player_positions, false);
+        }
+    }
+}
+
+//Spends the player's credits on the next rank and applies it to their ship straight away,
+//a ship waiting to respawn gets it when it comes back
+fn buy_upgrade(ecs: &mut World, slot: usize, upgrade: Upgrade) {
+    let upgrades = {
+        let mut game_data = ecs.write_storage::<GameData>();
+        let Some(data) = (&mut game_data).join().next() else { return; };
+        let (Some(credits), Some(upgrades)) = (data.credits.get_mut(slot), data.upgrades.get_mut(slot)) else { return; };
+        if !upgrades.buy(upgrade, credits) {
+            return;
+        }
+        upgrades.clone()
+    };
+
+    let mut players = ecs.write_storage::<components::Player>();
+    let mut speed_limits = ecs.write_storage::<components::SpeedLimit>();
+    let mut weapons = ecs.write_storage::<components::Weapon>();
+    for (player, speed_limit, weapon) in (&mut players, &mut speed_limits, &mut weapons).join() {
+        if player.slot != slot {
+            continue;
+        }
+        player.rotation_speed = PLAYER_ROTATION_SPEED * upgrades.scale(Upgrade::RotationSpeed);
+        speed_limit.max_speed = PLAYER_MAX_SPEED * upgrades.scale(Upgrade::MaxSpeed);
+        weapon.loadout = upgrades.loadout();
+        if upgrade == Upgrade::MaxHealth {
+            player.max_health += 1;
+            player.health += 1;
+        }
     }
 }
 
-pub fn update_movement(pos: &mut components::Position, player: &mut components::Player, delta_time: f64) {
+//Typed characters arrive through text_input, backspace and enter are read here
+fn update_name_entry(ecs: &mut World, input_manager: &mut HashMap<String, bool>) {
+    if input_manager::is_key_pressed(input_manager, "Backspace") {
+        input_manager::key_up(input_manager, "Backspace".to_string());
+        if let Screen::NameEntry(entry) = &mut *ecs.write_resource::<Screen>() {
+            entry.name.pop();
+        }
+    }
+    if input_manager::is_key_pressed(input_manager, "Return") {
+        input_manager::key_up(input_manager, "Return".to_string());
+        let screen = std::mem::take(&mut *ecs.write_resource::<Screen>());
+        if let Screen::NameEntry(mut entry) = screen {
+            if entry.name.trim().is_empty() {
+                entry.name = String::from("Pilot");
+            }
+            let mut high_scores = ecs.write_resource::<HighScores>();
+            high_scores.insert(entry);
+            if let Err(e) = high_scores.save(highscores::HIGH_SCORE_FILE) {
+                eprintln!("Could not save high scores: {}", e);
+            }
+        }
+    }
+}
 
-    player.current_speed *= player.friction;
-    player.current_speed += player.impulse;
+//Text typed while entering a name, main forwards SDL text input events here
+pub fn text_input(ecs: &mut World, text: &str) {
+    if let Screen::NameEntry(entry) = &mut *ecs.write_resource::<Screen>() {
+        for character in text.chars().filter(|character| !character.is_control()) {
+            if entry.name.chars().count() < highscores::MAX_NAME_LENGTH {
+                entry.name.push(character);
+            }
+        }
+    }
+}
 
-    if player.current_speed.length() > player.max_speed {
-        player.current_speed = player.current_speed.normalise();
-        player.current_speed = player.current_speed * player.max_speed;
+//Advances the run's clock, ending time attack runs when time is up and scoring survival runs by time survived
+fn update_run_clock(ecs: &mut World, mode: GameMode, delta_time: f64) {
+    let duration = ecs.read_resource::<Tuning>().time_attack_duration;
+    let mut time_up = false;
+    let mut level_up = None;
+    {
+        let mut game_data = ecs.write_storage::<GameData>();
+        for data in (&mut game_data).join() {
+            data.elapsed += delta_time;
+            match mode {
+                GameMode::TimeAttack => time_up = data.elapsed >= duration,
+                GameMode::Survival => {
+                    data.score = data.elapsed as u32;
+                    //There are no waves to clear so the level goes up with time instead
+                    let level = 1 + (data.elapsed / SURVIVAL_LEVEL_DURATION) as u32;
+                    if level > data.level {
+                        data.level = level;
+                        level_up = Some(level);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if time_up {
+        game_over(ecs);
+        return;
+    }
+    if let Some(level) = level_up {
+        ecs.write_resource::<GameEvents>().push(GameEvent::LevelReached(level));
+        for player in (&mut ecs.write_storage::<components::Player>()).join() {
+            player.missiles = missile::MISSILE_AMMO;
+        }
     }
+}
+
+//Keeps the next survival spawn waiting, they come in faster the longer the run lasts
+fn queue_survival_spawn(ecs: &mut World) {
+    let (level, elapsed) = {
+        let game_data = ecs.read_storage::<GameData>();
+        (&game_data).join().map(|data| (data.level, data.elapsed)).next().unwrap_or((1, 0.0))
+    };
+    let (start_interval, min_interval) = {
+        let tuning = ecs.read_resource::<Tuning>();
+        (tuning.survival_spawn_interval, tuning.survival_min_spawn_interval)
+    };
+    let difficulty = current_difficulty(ecs);
+    let mut wave = WaveDefinition::endless(level);
+    let speed_scale = difficulty.asteroid_speed_scale();
+    wave.asteroid_speed = wave.asteroid_speed.start * speed_scale..wave.asteroid_speed.end * speed_scale;
+    let interval = (start_interval - elapsed * SURVIVAL_SPAWN_RAMP).max(min_interval) / difficulty.asteroid_count_scale().max(0.1);
+
+    ecs.write_resource::<WaveSpawner>().trickle(&wave, interval);
+}
 
-    pos.x += player.current_speed.x * delta_time;
-    pos.y -= player.current_speed.y * delta_time;
+//Ends the run, asking for a name first if the score made the table
+fn game_over(ecs: &mut World) {
+    let entry = {
+        let game_data = ecs.read_storage::<GameData>();
+        (&game_data).join().next().map(|data| HighScoreEntry {
+            name: String::new(),
+            score: data.score,
+            level: data.level,
+            date: highscores::today(),
+            difficulty: data.difficulty,
+            mode: data.mode,
+        })
+    };
+    ecs.delete_all();
+    //Nothing that happened in this run may count towards the next one
+    ecs.write_resource::<GameEvents>().events.clear();
+    //Keep the lifetime stats from the last partial wave
+    if let Err(e) = ecs.read_resource::<Achievements>().save(achievements::ACHIEVEMENT_FILE) {
+        eprintln!("Could not save achievements: {}", e);
+    }
 
-    player.impulse = Vector2D::new(0.0, 0.0);
+    let screen = match entry {
+        Some(entry) if ecs.read_resource::<HighScores>().qualifies(entry.score, entry.mode) => Screen::NameEntry(entry),
+        _ => Screen::Title,
+    };
+    *ecs.write_resource::<Screen>() = screen;
 }
 
 pub fn load_world( ecs: &mut World) {
-    //Create Player
+    let (player_boundary, starting_lives, difficulty, mode) = {
+        let tuning = ecs.read_resource::<Tuning>();
+        (tuning.player_boundary, tuning.starting_lives, tuning.difficulty, tuning.game_mode)
+    };
+    //Versus always needs both ships
+    let player_count = match mode {
+        GameMode::Versus => 2,
+        _ => ecs.read_resource::<Tuning>().player_count.clamp(1, PLAYER_TEXTURES.len()),
+    };
+    //Start in the middle of the world so there is room to fly in every direction
+    let start_x = crate::WORLD_WIDTH as f64 / 2.0;
+    let start_y = crate::WORLD_HEIGHT as f64 / 2.0;
+    ecs.write_resource::<Camera>().snap_to(start_x, start_y);
+    *ecs.write_resource::<WaveSpawner>() = WaveSpawner::default();
+    ecs.write_resource::<GameEvents>().events.clear();
+    ecs.write_resource::<GameEvents>().push(GameEvent::RunStarted);
+
+    //Create Players side by side
+    if mode == GameMode::Versus {
+        start_versus_round(ecs, difficulty);
+    } else {
+        for slot in 0..player_count {
+            let offset = (slot as f64 - (player_count - 1) as f64 / 2.0) * COOP_START_SPACING;
+            build_player(ecs.create_entity(), start_x + offset, start_y, player_boundary, difficulty, slot, &Upgrades::default()).build();
+        }
+        //Asteroid
+        create_asteroid(ecs, components::Position { x: start_x + 150.0, y: start_y - 15.0, rot: 45.0 }, 100, 200.0, 200.0, AsteroidVariant::Plain);
+    }
+
     ecs.create_entity()
-        .with(components::Position { x: 350.0, y: 250.0, rot: 0.0 })
+        .with(components::GameData{
+            score: 0,
+            level: 1,
+            invincible_player: mode == GameMode::Zen,
+            lives: if mode == GameMode::Versus { 0 } else { (starting_lives.max(1) - 1) * player_count as u32 },
+            respawn_timers: vec![None; player_count],
+            player_scores: vec![0; player_count],
+            difficulty,
+            mode,
+            kills: vec![0; player_count],
+            round_wins: vec![0; player_count],
+            round_timer: None,
+            credits: vec![0; player_count],
+            upgrades: vec![Upgrades::default(); player_count],
+            elapsed: 0.0
+        })
+    .build();
+}
+
+pub fn build_player<B: Builder>(builder: B, x: f64, y: f64, boundary: components::BoundaryBehavior, difficulty: Difficulty, slot: usize, upgrades: &Upgrades) -> B {
+    builder
+        .with(components::Position { x, y, rot: 0.0 })
         .with(components::Renderable {
-            texture_name: String::from("Assets/Images/rocket.png"),
+            texture_name: PLAYER_TEXTURES[slot % PLAYER_TEXTURES.len()].to_string(),
             img_width: 276,
             img_height: 364,
             output_width: 60,
             output_height: 80,
-            img_rotation: 0.0
+            img_rotation: 0.0,
+            visible: true
         })
         .with(components::Player {
-            impulse: vector2d::Vector2D::new(0.0,0.0,),
-            current_speed: vector2d::Vector2D::new(0.0,0.0,),
-            rotation_speed: 200.0,
-            max_speed: 200.0,
-            friction: 0.9995,
-            health: 3
+            rotation_speed: PLAYER_ROTATION_SPEED * upgrades.scale(Upgrade::RotationSpeed),
+            thrust: 200.0 * movement::REFERENCE_FRAME_RATE,
+            health: difficulty.starting_health() + upgrades.bonus_health(),
+            max_health: difficulty.max_health() + upgrades.bonus_health(),
+            invulnerable: 0.0,
+            hyperspace_cooldown: 0.0,
+            missiles: missile::MISSILE_AMMO,
+            slot
         })
-    .build();
-    //Asteroid
-    ecs.create_entity()
-        .with(components::Position { x: 500.0, y: 235.0, rot: 45.0 })
-        .with(components::Renderable {
-            texture_name: String::from(get_random_asteroid_texture_name()),
-            img_width: 215,
-            img_height: 215,
-            output_width: 100,
-            output_height: 100,
-            img_rotation: 0.0
-        })
-        .with(components::Asteroid{
-            rotation_speed: 200.0,
-            speed: 200.0,
-            friction: 1.0,
-            quadrant: get_current_quadrant(&components::Position { x: 500.0, y: 235.0, rot: 45.0 })
-        })
-    .build();
-
-    ecs.create_entity()
-        .with(components::GameData{score: 0, level: 1, invincible_player: false})
-    .build();
+        .with(components::Velocity { x: 0.0, y: 0.0 })
+        .with(components::AngularVelocity { speed: 0.0 })
+        .with(components::Acceleration::default())
+        .with(components::Drag::from_frame_factor(0.9995))
+        .with(components::SpeedLimit { max_speed: PLAYER_MAX_SPEED * upgrades.scale(Upgrade::MaxSpeed) })
+        .with(boundary)
+        .with(components::PowerUps::default())
+        .with(components::Weapon::new(upgrades.loadout()))
 }
 
-const MAX_LASERS: usize = 200;
-fn fire_laser(ecs: &mut World, player_position: components::Position) {
-    {
-        let lasers = ecs.read_storage::<components::Laser>();
-        if lasers.count() > MAX_LASERS - 1 {
-            return;
-        }
-    }
-    ecs.create_entity()
-        .with(player_position)
-        .with(components::Renderable {
-            texture_name: String::from("Assets/Images/laser.png"),
-            img_width: 64,
-            img_height: 153,
-            output_width: 20,
-            output_height: 50,
-            img_rotation: 0.0
-         })
-        .with(components::Laser {
-            speed: 500.0
-    })
-    .build();
-}
-
-fn spawn_asteroids(ecs: &mut World, player_pos: &components::Position, forced: bool) {
+fn spawn_asteroids(ecs: &mut World, player_positions: &[components::Position], forced: bool) {
    if !forced {
-       {
+       let level = {
            let mut game_data = ecs.write_storage::<components::GameData>();
+           let mut level = 1;
            for gamedata in (&mut game_data).join() {
                gamedata.level += 1;
+               level = gamedata.level;
            }
+           level
+       };
+       ecs.write_resource::<GameEvents>().push(GameEvent::LevelReached(level));
+       let difficulty = current_difficulty(ecs);
+       let mut wave = ecs.read_resource::<Waves>().definition_for(level);
+       wave.asteroids = (wave.asteroids as f64 * difficulty.asteroid_count_scale()).round() as u32;
+       let speed_scale = difficulty.asteroid_speed_scale();
+       wave.asteroid_speed = wave.asteroid_speed.start * speed_scale..wave.asteroid_speed.end * speed_scale;
+       {
            let mut players = ecs.write_storage::<components::Player>();
            for player in (&mut players).join() {
-               if player.health < PLAYER_MAX_HEALTH {
-                   player.health += 1;
-               }
+               player.health = (player.health + wave.heal).min(player.max_health.max(player.health));
            }
        }
 
-       let amount_to_spawn: u32 = {
-           let game_data = ecs.read_storage::<components::GameData>();
-           let mut amount = 0;
-           for data in (&game_data).join() {
-               amount = data.level * 2;
-               //amount = 10000;
-           }
-           amount
-       };
-       for _ in 0..amount_to_spawn {
-           let spawn_position = generate_spawn_position(&player_pos);
-           let asteroid_speed = rand::thread_rng().gen_range(70.0..250.0);
-           let asteroid_rotation_speed = rand::thread_rng().gen_range(-400.0..400.0);
-           let asteroid_size = rand::thread_rng().gen_range(40..110);
-
-           create_asteroid(ecs, spawn_position, asteroid_size, asteroid_speed, asteroid_rotation_speed);
+       //Boss waves are just the boss and any pickups
+       if wave.boss {
+           let spawn_position = generate_spawn_position(player_positions);
+           boss::spawn_boss(ecs, spawn_position.x, spawn_position.y, level);
+           wave.asteroids = 0;
+           wave.enemies = 0;
        }
+       ecs.write_resource::<WaveSpawner>().queue_wave(&wave);
    }
    else
    {
        for _ in 0..1000 {
-           let spawn_position = generate_spawn_position(&player_pos);
+           let spawn_position = generate_spawn_position(player_positions);
            let asteroid_speed = rand::thread_rng().gen_range(70.0..250.0);
            let asteroid_rotation_speed = rand::thread_rng().gen_range(-400.0..400.0);
-           let asteroid_size = rand::thread_rng().gen_range(40..110);
+           let asteroid_size = rand::thread_rng().gen_range(asteroid::ASTEROID_MIN_SIZE..asteroid::ASTEROID_MAX_SIZE);
 
-           create_asteroid(ecs, spawn_position, asteroid_size, asteroid_speed, asteroid_rotation_speed);
+           create_asteroid(ecs, spawn_position, asteroid_size, asteroid_speed, asteroid_rotation_speed, AsteroidVariant::Plain);
        }
    }
 }
 
-fn create_asteroid(ecs: &mut World, position: components::Position, asteroid_size: u32, asteroid_speed: f64, asteroid_rotation_speed: f64){
+//Brings in whatever part of the current wave is due, away from the player
+fn release_wave_spawns(ecs: &mut World, player_positions: &[components::Position], delta_time: f64) {
+    let due = ecs.write_resource::<WaveSpawner>().release(delta_time);
+    if due.is_empty() {
+        return;
+    }
+    let level = {
+        let game_data = ecs.read_storage::<components::GameData>();
+        (&game_data).join().map(|data| data.level).next().unwrap_or(1)
+    };
+    let aggression = current_difficulty(ecs).enemy_aggression();
+
+    for spawn in due {
+        let spawn_position = generate_spawn_position(player_positions);
+        match spawn {
+            WaveSpawn::Asteroid { size, speed, rotation_speed, variant } => {
+                create_asteroid(ecs, spawn_position, size, speed, rotation_speed, variant);
+            }
+            WaveSpawn::Enemy => {
+                enemy::build_ufo(ecs.create_entity(), spawn_position.x, spawn_position.y, level, aggression).build();
+            }
+            WaveSpawn::GravityWell { drifting } => {
+                gravity::build_gravity_well(ecs.create_entity(), spawn_position.x, spawn_position.y, drifting).build();
+            }
+            WaveSpawn::Pickup(kind) => {
+                let lifetime = ecs.read_resource::<Tuning>().pickup_lifetime;
+                pickup::build_pickup(ecs.create_entity(), spawn_position.x, spawn_position.y, kind, lifetime).build();
+            }
+        }
+    }
+}
+
+fn create_asteroid(ecs: &mut World, position: components::Position, asteroid_size: u32, asteroid_speed: f64, asteroid_rotation_speed: f64, variant: AsteroidVariant){
     // Calculate adjusted position to keep the entire asteroid within the screen bounds
     let half_size = asteroid_size as f64 / 2.0;
-    let adjusted_x = position.x.max(half_size).min(crate::SCREEN_WIDTH as f64 - half_size);
-    let adjusted_y = position.y.max(half_size).min(crate::SCREEN_HEIGHT as f64 - half_size);
+    let boundary = ecs.read_resource::<Tuning>().asteroid_boundary;
+    let adjusted_x = position.x.max(half_size).min(crate::WORLD_WIDTH as f64 - half_size);
+    let adjusted_y = position.y.max(half_size).min(crate::WORLD_HEIGHT as f64 - half_size);
 
-    ecs.create_entity()
-        .with(components::Position {
-            x: adjusted_x,
-            y: adjusted_y,
-            rot: position.rot,
-        })
-        .with(components::Renderable {
-            texture_name: String::from(get_random_asteroid_texture_name()),
-            img_width: 215,
-            img_height: 215,
-            output_width: asteroid_size,
-            output_height: asteroid_size,
-            img_rotation: 0.0
-        })
-        .with(components::Asteroid{
-            rotation_speed: asteroid_rotation_speed,
-            speed: asteroid_speed,
-            friction: 1.0,
-            quadrant: get_current_quadrant(&position)
-        })
+    asteroid::build_asteroid(ecs.create_entity(), adjusted_x, adjusted_y, position.rot, asteroid_size, asteroid_speed, asteroid_rotation_speed, boundary, variant)
     .build();
 }
 
-fn generate_spawn_position(player_pos: &components::Position) -> components::Position {
-    let player_quadrant = get_current_quadrant(player_pos);
-
-    // Determine quadrant based on rng. Can never be the player quadrant
-    let random_chance: f64 = rand::thread_rng().gen_range(0.0..1.0);
-
-    let asteroid_spawn_quadrant = match player_quadrant {
-        Quadrant::TopLeft => {
-            if random_chance < 0.5 {
-                Quadrant::BottomRight
-            } else if random_chance < 0.75 {
-                Quadrant::TopRight
-            } else {
-                Quadrant::BottomLeft
-            }
-        },
-        Quadrant::TopRight => {
-            if random_chance < 0.5 {
-                Quadrant::BottomLeft
-            } else if random_chance < 0.75 {
-                Quadrant::TopLeft
-            } else {
-                Quadrant::BottomRight
-            }
-        },
-        Quadrant::BottomLeft => {
-            if random_chance < 0.5 {
-                Quadrant::TopRight
-            } else if random_chance < 0.75 {
-                Quadrant::BottomRight
-            } else {
-                Quadrant::TopLeft
-            }
-        },
-        Quadrant::BottomRight => {
-            if random_chance < 0.5 {
-                Quadrant::TopLeft
-            } else if random_chance < 0.75 {
-                Quadrant::BottomLeft
-            } else {
-                Quadrant::TopRight
-            }
-        },
-    };
+fn generate_spawn_position(player_positions: &[components::Position]) -> components::Position {
+    let player_quadrants: Vec<Quadrant> = player_positions.iter().map(get_current_quadrant).collect();
+
+    //Never spawn in a quadrant with a ship in it, quadrants opposite a ship are twice as likely
+    let candidates: Vec<(Quadrant, usize)> = ALL_QUADRANTS.iter()
+        .filter(|quadrant| !player_quadrants.contains(quadrant))
+        .map(|quadrant| (*quadrant, 1 + player_quadrants.iter().filter(|player| opposite_quadrant(**player) == *quadrant).count()))
+        .collect();
+    //Every quadrant has a ship in it, fall back to anywhere
+    let candidates = if candidates.is_empty() { ALL_QUADRANTS.iter().map(|quadrant| (*quadrant, 1)).collect() } else { candidates };
+
+    let total_weight: usize = candidates.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rand::thread_rng().gen_range(0..total_weight);
+    let mut asteroid_spawn_quadrant = candidates[0].0;
+    for (quadrant, weight) in &candidates {
+        if roll < *weight {
+            asteroid_spawn_quadrant = *quadrant;
+            break;
+        }
+        roll -= weight;
+    }
 
     //Get random point within the selected part of the screen
     let (spawn_x, spawn_y) = match asteroid_spawn_quadrant {
         Quadrant::TopLeft => (
-            rand::thread_rng().gen_range(0.0..crate::SCREEN_WIDTH as f64 / 2.0),
-            rand::thread_rng().gen_range(0.0..crate::SCREEN_HEIGHT as f64 / 2.0),
+            rand::thread_rng().gen_range(0.0..crate::WORLD_WIDTH as f64 / 2.0),
+            rand::thread_rng().gen_range(0.0..crate::WORLD_HEIGHT as f64 / 2.0),
         ),
         Quadrant::TopRight => (
-            rand::thread_rng().gen_range(crate::SCREEN_WIDTH as f64 / 2.0..crate::SCREEN_WIDTH as f64),
-            rand::thread_rng().gen_range(0.0..crate::SCREEN_HEIGHT as f64 / 2.0),
+            rand::thread_rng().gen_range(crate::WORLD_WIDTH as f64 / 2.0..crate::WORLD_WIDTH as f64),
+            rand::thread_rng().gen_range(0.0..crate::WORLD_HEIGHT as f64 / 2.0),
         ),
         Quadrant::BottomLeft => (
-            rand::thread_rng().gen_range(0.0..crate::SCREEN_WIDTH as f64 / 2.0),
-            rand::thread_rng().gen_range(crate::SCREEN_HEIGHT as f64 / 2.0..crate::SCREEN_HEIGHT as f64),
+            rand::thread_rng().gen_range(0.0..crate::WORLD_WIDTH as f64 / 2.0),
+            rand::thread_rng().gen_range(crate::WORLD_HEIGHT as f64 / 2.0..crate::WORLD_HEIGHT as f64),
         ),
         Quadrant::BottomRight => (
-            rand::thread_rng().gen_range(crate::SCREEN_WIDTH as f64 / 2.0..crate::SCREEN_WIDTH as f64),
-            rand::thread_rng().gen_range(crate::SCREEN_HEIGHT as f64 / 2.0..crate::SCREEN_HEIGHT as f64),
+            rand::thread_rng().gen_range(crate::WORLD_WIDTH as f64 / 2.0..crate::WORLD_WIDTH as f64),
+            rand::thread_rng().gen_range(crate::WORLD_HEIGHT as f64 / 2.0..crate::WORLD_HEIGHT as f64),
         ),
     };
 
@@ -343,9 +659,18 @@ fn generate_spawn_position(player_pos: &components::Position) -> components::Pos
     }
 }
 
+fn opposite_quadrant(quadrant: Quadrant) -> Quadrant {
+    match quadrant {
+        Quadrant::TopLeft => Quadrant::BottomRight,
+        Quadrant::TopRight => Quadrant::BottomLeft,
+        Quadrant::BottomLeft => Quadrant::TopRight,
+        Quadrant::BottomRight => Quadrant::TopLeft,
+    }
+}
+
 pub fn get_current_quadrant(pos: &components::Position) -> Quadrant {
-    if pos.x < crate::SCREEN_WIDTH as f64 / 2.0 {
-        if pos.y < crate::SCREEN_HEIGHT as f64 / 2.0 {
+    if pos.x < crate::WORLD_WIDTH as f64 / 2.0 {
+        if pos.y < crate::WORLD_HEIGHT as f64 / 2.0 {
             //println!("Player is Top left");
             Quadrant::TopLeft
         } else {
@@ -353,7 +678,7 @@ pub fn get_current_quadrant(pos: &components::Position) -> Quadrant {
             Quadrant::BottomLeft
         }
     } else {
-        if pos.y < crate::SCREEN_HEIGHT as f64 / 2.0 {
+        if pos.y < crate::WORLD_HEIGHT as f64 / 2.0 {
             //println!("Player is Top Right");
             Quadrant::TopRight
         } else {
@@ -363,21 +688,273 @@ pub fn get_current_quadrant(pos: &components::Position) -> Quadrant {
     }
 }
 
-fn get_random_asteroid_texture_name() -> String {
-    let random_number = rand::thread_rng().gen_range(1..=3);
-    format!("Assets/Images/asteroid_{}.png", random_number)
-}
+//A lost ship comes back after a delay if the shared pool has one to spare,
+//the run is over once no ship is left flying or waiting to respawn
+fn handle_player_death(ecs: &mut World, delta_time: f64) {
+    let alive: Vec<usize> = {
+        let players = ecs.read_storage::<components::Player>();
+        players.join().map(|player| player.slot).collect()
+    };
+    let (respawn_delay, safe_radius, player_boundary) = {
+        let tuning = ecs.read_resource::<Tuning>();
+        (tuning.respawn_delay, tuning.respawn_safe_radius, tuning.player_boundary)
+    };
 
-fn reload_world_if_no_players(ecs: &mut World) {
-    let mut must_reload_world = false;
+    let mut run_over = true;
+    let mut respawns = Vec::new();
     {
+        let mut game_data = ecs.write_storage::<GameData>();
+        for data in (&mut game_data).join() {
+            for slot in 0..data.respawn_timers.len() {
+                if alive.contains(&slot) {
+                    continue;
+                }
+                match data.respawn_timers[slot] {
+                    //The ship was just lost, or is out of the run if there was none to spare
+                    None => {
+                        if data.lives > 0 {
+                            data.lives -= 1;
+                            data.respawn_timers[slot] = Some(respawn_delay);
+                        }
+                    }
+                    Some(remaining) if remaining - delta_time <= 0.0 => {
+                        data.respawn_timers[slot] = None;
+                        respawns.push(slot);
+                    }
+                    Some(remaining) => data.respawn_timers[slot] = Some(remaining - delta_time),
+                }
+            }
+            run_over = alive.is_empty() && respawns.is_empty() && data.respawn_timers.iter().all(Option::is_none);
+        }
+    }
+    if run_over {
+        game_over(ecs);
+        return;
+    }
+    for slot in respawns {
+        let (x, y) = find_safe_spawn(ecs, safe_radius);
+        let difficulty = current_difficulty(ecs);
+        let upgrades = {
+            let game_data = ecs.read_storage::<GameData>();
+            (&game_data).join().next().and_then(|data| data.upgrades.get(slot).cloned()).unwrap_or_default()
+        };
+        if alive.is_empty() {
+            ecs.write_resource::<Camera>().snap_to(x, y);
+        }
+        build_player(ecs.create_entity(), x, y, player_boundary, difficulty, slot, &upgrades).build();
+    }
+}
+
+//A round is decided once at most one ship is left, after a short pause the next one starts
+//or the match ends when someone has won enough rounds
+fn update_versus_round(ecs: &mut World, delta_time: f64) {
+    let alive: Vec<usize> = {
         let players = ecs.read_storage::<components::Player>();
-        if players.join().count() < 1 {
-            must_reload_world = true;
+        players.join().map(|player| player.slot).collect()
+    };
+    let rounds_to_win = ecs.read_resource::<Tuning>().versus_rounds_to_win.max(1);
+
+    let mut next_round = false;
+    let mut match_over = false;
+    {
+        let mut game_data = ecs.write_storage::<GameData>();
+        for data in (&mut game_data).join() {
+            match data.round_timer {
+                None => {
+                    if alive.len() <= 1 {
+                        //Both ships going down together is a draw
+                        if let Some(round_win) = alive.first().and_then(|winner| data.round_wins.get_mut(*winner)) {
+                            *round_win += 1;
+                        }
+                        data.round_timer = Some(VERSUS_ROUND_END_DELAY);
+                    }
+                }
+                Some(remaining) if remaining - delta_time <= 0.0 => {
+                    data.round_timer = None;
+                    if data.round_wins.iter().any(|wins| *wins >= rounds_to_win) {
+                        match_over = true;
+                    } else {
+                        next_round = true;
+                    }
+                }
+                Some(remaining) => data.round_timer = Some(remaining - delta_time),
+            }
         }
     }
-    if must_reload_world {
+
+    if match_over {
         ecs.delete_all();
-        load_world(ecs);
+        *ecs.write_resource::<Screen>() = Screen::Title;
+    } else if next_round {
+        //Clear out the last round's ships, shots, blasts and cover
+        {
+            let entities = ecs.entities();
+            let players = ecs.read_storage::<components::Player>();
+            let lasers = ecs.read_storage::<components::Laser>();
+            let asteroids = ecs.read_storage::<components::Asteroid>();
+            let pickups = ecs.read_storage::<components::Pickup>();
+            let missiles = ecs.read_storage::<components::Missile>();
+            let explosions = ecs.read_storage::<components::Explosion>();
+            for (entity, _) in (&entities, players.mask() | lasers.mask() | asteroids.mask() | pickups.mask() | missiles.mask() | explosions.mask()).join() {
+                entities.delete(entity).ok();
+            }
+        }
+        let difficulty = current_difficulty(ecs);
+        start_versus_round(ecs, difficulty);
+    }
+}
+
+//Puts both ships at opposite ends of the middle of the arena with some asteroids around for cover
+fn start_versus_round(ecs: &mut World, difficulty: Difficulty) {
+    let player_boundary = ecs.read_resource::<Tuning>().player_boundary;
+    let start_x = crate::WORLD_WIDTH as f64 / 2.0;
+    let start_y = crate::WORLD_HEIGHT as f64 / 2.0;
+    ecs.write_resource::<Camera>().snap_to(start_x, start_y);
+
+    let mut starts = Vec::new();
+    for slot in 0..2 {
+        let x = start_x + (slot as f64 - 0.5) * VERSUS_START_SPACING;
+        build_player(ecs.create_entity(), x, start_y, player_boundary, difficulty, slot, &Upgrades::default()).build();
+        starts.push(components::Position { x, y: start_y, rot: 0.0 });
+    }
+    for _ in 0..VERSUS_ASTEROIDS {
+        let spawn_position = generate_spawn_position(&starts);
+        let asteroid_size = rand::thread_rng().gen_range(asteroid::ASTEROID_MIN_SIZE..asteroid::ASTEROID_MAX_SIZE);
+        create_asteroid(ecs, spawn_position, asteroid_size, 80.0, 100.0, AsteroidVariant::Plain);
     }
-}
\ No newline at end of file
+}
+
+//Credits the shooter when a versus kill lands
+pub fn credit_kill(game_data: &mut GameData, shooter: usize) {
+    if let Some(kills) = game_data.kills.get_mut(shooter) {
+        *kills += 1;
+    }
+    game_data.add_score(Some(shooter), VERSUS_KILL_SCORE);
+}
+
+//Mode of the run in progress
+pub fn current_mode(ecs: &World) -> GameMode {
+    let game_data = ecs.read_storage::<GameData>();
+    (&game_data).join().map(|data| data.mode).next().unwrap_or(GameMode::Standard)
+}
+
+//Difficulty of the run in progress
+pub fn current_difficulty(ecs: &World) -> Difficulty {
+    let game_data = ecs.read_storage::<GameData>();
+    (&game_data).join().map(|data| data.difficulty).next().unwrap_or(Difficulty::Normal)
+}
+
+//Jumps the ship to a random spot clear of asteroids. A malfunction drops it anywhere instead and damages it
+fn hyperspace(ecs: &mut World, entity: Entity) {
+    let (cooldown, malfunction_chance, safe_radius) = {
+        let tuning = ecs.read_resource::<Tuning>();
+        (tuning.hyperspace_cooldown, tuning.hyperspace_malfunction_chance, tuning.respawn_safe_radius)
+    };
+    let malfunction = rand::thread_rng().gen_bool(malfunction_chance.clamp(0.0, 1.0));
+    let (x, y) = if malfunction {
+        (rand::thread_rng().gen_range(0.0..crate::WORLD_WIDTH as f64), rand::thread_rng().gen_range(0.0..crate::WORLD_HEIGHT as f64))
+    } else {
+        find_safe_spawn(ecs, safe_radius)
+    };
+
+    let departure = {
+        let mut positions = ecs.write_storage::<components::Position>();
+        let Some(pos) = positions.get_mut(entity) else { return; };
+        let departure = (pos.x, pos.y);
+        pos.x = x;
+        pos.y = y;
+        departure
+    };
+    if let Some(velocity) = ecs.write_storage::<components::Velocity>().get_mut(entity) {
+        velocity.x = 0.0;
+        velocity.y = 0.0;
+    }
+    let invincible = {
+        let game_data = ecs.read_storage::<GameData>();
+        (&game_data).join().any(|data| data.invincible_player)
+    };
+    if let Some(player) = ecs.write_storage::<components::Player>().get_mut(entity) {
+        player.hyperspace_cooldown = cooldown;
+        if malfunction && !invincible {
+            player.health -= 1;
+            if player.health < 1 {
+                ecs.entities().delete(entity).ok();
+            }
+        }
+    }
+
+    for (effect_x, effect_y) in [departure, (x, y)] {
+        ecs.create_entity()
+            .with(components::Position { x: effect_x, y: effect_y, rot: 0.0 })
+            .with(components::Renderable {
+                texture_name: String::from("Assets/Images/hyperspace.png"),
+                img_width: 128,
+                img_height: 128,
+                output_width: 140,
+                output_height: 140,
+                img_rotation: 0.0,
+                visible: true
+            })
+            .with(components::AngularVelocity { speed: 360.0 })
+            .with(components::Lifetime { remaining: HYPERSPACE_EFFECT_DURATION })
+        .build();
+    }
+
+    //The camera follows the other ship too in co-op, so only jump it along when flying alone
+    let alone = ecs.read_storage::<components::Player>().join().count() == 1;
+    let mut camera = ecs.write_resource::<Camera>();
+    if alone {
+        camera.snap_to(x, y);
+    }
+    camera.zoom_pulse(0.92, HYPERSPACE_EFFECT_DURATION);
+    if malfunction {
+        camera.add_trauma(0.5);
+    }
+}
+
+//Where every ship is, new spawns are kept clear of them
+fn player_positions(ecs: &World) -> Vec<components::Position> {
+    let players = ecs.read_storage::<components::Player>();
+    let positions = ecs.read_storage::<components::Position>();
+
+    (&positions, &players).join()
+        .map(|(pos, _)| components::Position { x: pos.x, y: pos.y, rot: pos.rot })
+        .collect()
+}
+
+//Looks for a spot with no asteroids, enemies or bosses within radius. Tries the middle of the world first
+//and falls back to the spot furthest from any hazard if nowhere is clear
+fn find_safe_spawn(ecs: &World, radius: f64) -> (f64, f64) {
+    let positions = ecs.read_storage::<components::Position>();
+    let asteroids = ecs.read_storage::<components::Asteroid>();
+    let enemies = ecs.read_storage::<components::Enemy>();
+    let bosses = ecs.read_storage::<components::Boss>();
+    let parts = ecs.read_storage::<components::BossPart>();
+    let hazards: Vec<(f64, f64)> = (&positions, asteroids.mask() | enemies.mask() | bosses.mask() | parts.mask()).join()
+        .map(|(pos, _)| (pos.x, pos.y))
+        .collect();
+
+    let clearance = |x: f64, y: f64| {
+        hazards.iter()
+            .map(|(hazard_x, hazard_y)| ((hazard_x - x).powi(2) + (hazard_y - y).powi(2)).sqrt())
+            .fold(f64::MAX, f64::min)
+    };
+
+    let margin = radius.min(crate::WORLD_WIDTH.min(crate::WORLD_HEIGHT) as f64 / 4.0);
+    let mut best = (crate::WORLD_WIDTH as f64 / 2.0, crate::WORLD_HEIGHT as f64 / 2.0);
+    let mut best_clearance = clearance(best.0, best.1);
+    let mut rng = rand::thread_rng();
+    for _ in 0..RESPAWN_ATTEMPTS {
+        if best_clearance >= radius {
+            break;
+        }
+        let x = rng.gen_range(margin..crate::WORLD_WIDTH as f64 - margin);
+        let y = rng.gen_range(margin..crate::WORLD_HEIGHT as f64 - margin);
+        let candidate_clearance = clearance(x, y);
+        if candidate_clearance > best_clearance {
+            best = (x, y);
+            best_clearance = candidate_clearance;
+        }
+    }
+    best
+}
@@ -0,0 +1,129 @@
+use rand::Rng;
+use specs::prelude::*;
+use specs::{Entities, Join};
+
+use crate::{components, damage, movement};
+use crate::camera::Camera;
+use crate::achievements::GameEvents;
+use crate::tuning::Tuning;
+
+//Pull of a well is strength / distance squared, at 200 units from the core this is about 300 units per second squared
+const WELL_STRENGTH: f64 = 1.2e7;
+const WELL_CORE_RADIUS: f64 = 40.0;
+//Beyond this the pull is too weak to bother with
+const WELL_RANGE: f64 = 1200.0;
+const WELL_SIZE: u32 = 320;
+//Drifting wells move this slowly, the rest stay put
+const WELL_DRIFT_SPEED: f64 = 40.0;
+
+pub fn build_gravity_well<B: Builder>(builder: B, x: f64, y: f64, drifting: bool) -> B {
+    let mut rng = rand::thread_rng();
+    let speed = if drifting { WELL_DRIFT_SPEED } else { 0.0 };
+    builder
+        .with(components::Position { x, y, rot: 0.0 })
+        .with(components::Renderable {
+            texture_name: String::from("Assets/Images/black_hole.png"),
+            img_width: 256,
+            img_height: 256,
+            output_width: WELL_SIZE,
+            output_height: WELL_SIZE,
+            img_rotation: 0.0,
+            visible: true
+        })
+        .with(components::GravityWell { strength: WELL_STRENGTH, core_radius: WELL_CORE_RADIUS, range: WELL_RANGE })
+        .with(movement::velocity_from_heading(rng.gen_range(0.0..360.0), speed))
+        .with(components::AngularVelocity { speed: 60.0 })
+        .with(components::BoundaryBehavior::Bounce)
+}
+
+//Pulls players, asteroids and lasers towards every gravity well. Asteroids and lasers reaching a core are swallowed,
+//ships take a hit like from any other hazard
+pub struct Gravity;
+
+impl<'a> System<'a> for Gravity {
+    type SystemData = (
+        WriteStorage<'a, components::Position>,
+        WriteStorage<'a, components::Velocity>,
+        WriteStorage<'a, components::Acceleration>,
+        ReadStorage<'a, components::GravityWell>,
+        WriteStorage<'a, components::Player>,
+        ReadStorage<'a, components::PowerUps>,
+        ReadStorage<'a, components::Asteroid>,
+        ReadStorage<'a, components::Laser>,
+        ReadStorage<'a, components::GameData>,
+        Entities<'a>,
+        Write<'a, Camera>,
+        ReadExpect<'a, Tuning>,
+        Write<'a, GameEvents>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (mut positions, mut velocities, mut accelerations, wells, mut players, power_ups, asteroids, lasers, game_data, entities, mut camera, tuning, mut events) = data;
+
+        let wells: Vec<(f64, f64, components::GravityWell)> = (&positions, &wells).join()
+            .map(|(pos, well)| (pos.x, pos.y, well.clone()))
+            .collect();
+        if wells.is_empty() {
+            return;
+        }
+        let invincible_player = (&game_data).join().any(|data| data.invincible_player);
+
+        for (pos, mut player, power_ups, laser, asteroid, mut velocity, entity) in (&mut positions, (&mut players).maybe(), power_ups.maybe(), lasers.maybe(), asteroids.maybe(), (&mut velocities).maybe(), &entities).join() {
+            if player.is_none() && laser.is_none() && asteroid.is_none() {
+                continue;
+            }
+            let (mut pull_x, mut pull_y) = (0.0, 0.0);
+            let mut in_range = false;
+            for (well_x, well_y, well) in &wells {
+                let diff_x = well_x - pos.x;
+                let diff_y = well_y - pos.y;
+                let distance_squared = diff_x * diff_x + diff_y * diff_y;
+                if distance_squared > well.range * well.range {
+                    continue;
+                }
+                in_range = true;
+
+                if distance_squared < well.core_radius * well.core_radius {
+                    match player.as_deref_mut() {
+                        Some(player) => {
+                            if invincible_player {
+                                continue;
+                            }
+                            let shielded = power_ups.is_some_and(|power_ups| power_ups.shield > 0.0);
+                            let target = damage::PlayerTarget { player, entity, pos: &*pos, velocity: velocity.as_deref_mut(), shielded };
+                            damage::damage_player(target, (*well_x, *well_y), &entities, &mut camera, &tuning, &mut events);
+                        },
+                        None => {
+                            entities.delete(entity).ok();
+                        }
+                    }
+                    break;
+                }
+
+                //Clamped at the core so the pull never blows up close in
+                let distance = distance_squared.sqrt();
+                let pull = well.strength / distance_squared.max(well.core_radius * well.core_radius);
+                pull_x += diff_x / distance * pull;
+                pull_y += diff_y / distance * pull;
+            }
+            if !in_range {
+                continue;
+            }
+
+            //Lasers and most asteroids have no acceleration of their own, they get one the first time a well reaches them
+            match accelerations.get_mut(entity) {
+                Some(acceleration) => {
+                    acceleration.x += pull_x;
+                    acceleration.y += pull_y;
+                },
+                None => {
+                    accelerations.insert(entity, components::Acceleration { x: pull_x, y: pull_y }).ok();
+                }
+            }
+
+            //Bent lasers point the way they are now flying
+            if let (Some(_), Some(velocity)) = (laser, velocity) {
+                pos.rot = movement::rotation_of(velocity.x, velocity.y);
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::tuning::{Difficulty, GameMode};
+
+pub const HIGH_SCORE_FILE: &str = "highscores.txt";
+//How many entries each mode's table keeps
+pub const MAX_ENTRIES: usize = 10;
+pub const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Clone)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: u32,
+    pub level: u32,
+    //Day the run ended as YYYY-MM-DD
+    pub date: String,
+    pub difficulty: Difficulty,
+    //Each mode has its own table
+    pub mode: GameMode,
+}
+
+//Best runs of every mode, highest score first
+#[derive(Default)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    //One tab separated entry per line: name, score, level, date, difficulty, mode.
+    //Lines without a mode are from before there were modes and count as standard.
+    //A missing file is an empty table and lines that do not parse are skipped
+    pub fn load(path: &str) -> HighScores {
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return HighScores::default();
+        };
+        HighScores::parse(&text)
+    }
+
+    fn parse(text: &str) -> HighScores {
+        let mut high_scores = HighScores::default();
+        for line in text.lines() {
+            let mut fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() == 5 {
+                fields.push(GameMode::Standard.name());
+            }
+            if let [name, score, level, date, difficulty, mode] = fields[..] {
+                if let (Ok(score), Ok(level), Some(difficulty), Some(mode)) = (score.parse(), level.parse(), Difficulty::from_name(difficulty), GameMode::from_name(mode)) {
+                    high_scores.insert(HighScoreEntry { name: name.to_string(), score, level, date: date.to_string(), difficulty, mode });
+                }
+            }
+        }
+        high_scores
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text: String = self.entries.iter()
+            .map(|entry| format!("{}\t{}\t{}\t{}\t{}\t{}\n", entry.name, entry.score, entry.level, entry.date, entry.difficulty.name(), entry.mode.name()))
+            .collect();
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn entries_for(&self, mode: GameMode) -> impl Iterator<Item = &HighScoreEntry> {
+        self.entries.iter().filter(move |entry| entry.mode == mode)
+    }
+
+    //Whether a run with this score would make it onto the mode's table
+    pub fn qualifies(&self, score: u32, mode: GameMode) -> bool {
+        score > 0 && (self.entries_for(mode).count() < MAX_ENTRIES || self.entries_for(mode).any(|entry| score > entry.score))
+    }
+
+    pub fn insert(&mut self, entry: HighScoreEntry) {
+        let mode = entry.mode;
+        //Ties go below the existing entries
+        let index = self.entries.iter().position(|existing| entry.score > existing.score).unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+
+        let mut kept = 0;
+        self.entries.retain(|entry| {
+            if entry.mode != mode {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_ENTRIES
+        });
+    }
+}
+
+//Today's date in UTC as YYYY-MM-DD
+pub fn today() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    date_from_days((seconds / 86_400) as i64)
+}
+
+fn date_from_days(days: i64) -> String {
+    //Days since 1970-01-01 to a civil date, counting in 400 year eras that start on March 1st
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u32, mode: GameMode) -> HighScoreEntry {
+        HighScoreEntry { name: name.to_string(), score, level: 1, date: String::from("2024-01-01"), difficulty: Difficulty::Normal, mode }
+    }
+
+    #[test]
+    fn parse_skips_malformed_lines() {
+        let high_scores = HighScores::parse(concat!(
+            "Ann\t500\t4\t2024-01-01\tHard\tSurvival\n",
+            "Old\t300\t3\t2023-06-01\tNormal\n",
+            "\n",
+            "Bad Score\tlots\t3\t2024-01-01\tNormal\tStandard\n",
+            "Bad Difficulty\t100\t3\t2024-01-01\tNightmare\tStandard\n",
+            "Bad Mode\t100\t3\t2024-01-01\tNormal\tArcade\n",
+            "Too Few\t100\t3\n",
+            "Too Many\t100\t3\t2024-01-01\tNormal\tStandard\textra\n",
+        ));
+
+        assert_eq!(high_scores.entries.len(), 2);
+        let survival: Vec<&HighScoreEntry> = high_scores.entries_for(GameMode::Survival).collect();
+        assert_eq!(survival.len(), 1);
+        assert_eq!((survival[0].name.as_str(), survival[0].score, survival[0].level), ("Ann", 500, 4));
+        assert!(survival[0].difficulty == Difficulty::Hard);
+
+        //Entries from before modes existed count as standard
+        let standard: Vec<&HighScoreEntry> = high_scores.entries_for(GameMode::Standard).collect();
+        assert_eq!(standard.len(), 1);
+        assert_eq!(standard[0].name, "Old");
+    }
+
+    #[test]
+    fn insert_keeps_the_best_entries_of_each_mode() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(entry("Zen", 5, GameMode::Zen));
+        for score in 1..=MAX_ENTRIES as u32 + 2 {
+            high_scores.insert(entry("Player", score * 100, GameMode::Standard));
+        }
+
+        let scores: Vec<u32> = high_scores.entries_for(GameMode::Standard).map(|entry| entry.score).collect();
+        let expected: Vec<u32> = (3..=MAX_ENTRIES as u32 + 2).rev().map(|score| score * 100).collect();
+        assert_eq!(scores, expected);
+        //A full standard table never pushes out other modes
+        assert_eq!(high_scores.entries_for(GameMode::Zen).count(), 1);
+    }
+
+    #[test]
+    fn ties_go_below_existing_entries() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(entry("First", 100, GameMode::Standard));
+        high_scores.insert(entry("Second", 100, GameMode::Standard));
+
+        let names: Vec<&str> = high_scores.entries_for(GameMode::Standard).map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["First", "Second"]);
+    }
+
+    #[test]
+    fn qualifies_only_when_the_score_beats_a_full_table() {
+        let mut high_scores = HighScores::default();
+        assert!(!high_scores.qualifies(0, GameMode::Standard));
+        assert!(high_scores.qualifies(1, GameMode::Standard));
+
+        for score in 1..=MAX_ENTRIES as u32 {
+            high_scores.insert(entry("Player", score * 100, GameMode::Standard));
+        }
+        assert!(!high_scores.qualifies(100, GameMode::Standard));
+        assert!(high_scores.qualifies(101, GameMode::Standard));
+        //Other modes still have room
+        assert!(high_scores.qualifies(1, GameMode::TimeAttack));
+    }
+
+    #[test]
+    fn converts_days_since_epoch_to_dates() {
+        assert_eq!(date_from_days(0), "1970-01-01");
+        assert_eq!(date_from_days(59), "1970-03-01");
+        assert_eq!(date_from_days(11_016), "2000-02-29");
+        assert_eq!(date_from_days(20_088), "2024-12-31");
+        assert_eq!(date_from_days(-1), "1969-12-31");
+    }
+}
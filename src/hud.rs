@@ -0,0 +1,319 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+use specs::prelude::*;
+
+use crate::components;
+use crate::game::Screen;
+use crate::highscores::HighScores;
+use crate::achievements::{Achievements, ALL_ACHIEVEMENTS};
+use crate::input_manager::{InputAction, PlayerBindings};
+use crate::shop::{ALL_UPGRADES, MAX_RANK};
+use crate::tuning::{Tuning, FireMode, GameMode};
+use crate::{SCREEN_WIDTH, SCREEN_HEIGHT};
+
+pub struct TextureRectTuple<'a> {
+    pub texture: Texture<'a>,
+    pub rect: Rect,
+}
+
+//Whole seconds the clock shows, counting down in time attack and up in survival. None in modes without a clock
+pub fn clock_seconds(ecs: &World) -> Option<u32> {
+    let game_data = ecs.read_storage::<components::GameData>();
+    let game_data = (&game_data).join().next()?;
+    match game_data.mode {
+        GameMode::TimeAttack => Some((ecs.read_resource::<Tuning>().time_attack_duration - game_data.elapsed).max(0.0).ceil() as u32),
+        GameMode::Survival => Some(game_data.elapsed as u32),
+        _ => None,
+    }
+}
+
+//Rebuilds every HUD texture, this is expensive so main only calls it every few frames
+pub fn build_hud<'a>(ecs: &World, font: &Font, texture_creator: &'a TextureCreator<WindowContext>, fps: u64) -> Result<Vec<TextureRectTuple<'a>>, String> {
+    let mut ui_textures: Vec<TextureRectTuple> = Vec::new();
+    let white = Color::RGB(255, 255, 255);
+
+    //Health
+    let players = ecs.read_storage::<components::Player>();
+    let power_ups = ecs.read_storage::<components::PowerUps>();
+    let weapons = ecs.read_storage::<components::Weapon>();
+    let player_bindings = ecs.read_resource::<PlayerBindings>();
+    for (player, power_ups, weapon) in (&players, power_ups.maybe(), weapons.maybe()).join() {
+        //The second player gets a panel on the right under the first player's power ups
+        let second = player.slot > 0;
+        let panel_x = (SCREEN_WIDTH - 290) as i32;
+        if second {
+            let health_text = format!("P{} Health: {}", player.slot + 1, player.health);
+            ui_textures.push(text_line(font, texture_creator, &health_text, white, panel_x, 250, 36)?);
+        } else {
+            let health_text = "Health: ".to_string() + &player.health.to_string();
+            ui_textures.push(text_texture(font, texture_creator, &health_text, white, Rect::new(panel_x, 0i32, 110u32, 50u32))?);
+        }
+
+        if let Some(weapon) = weapon {
+            let weapon_text = format!("Weapon {}: {}", weapon.selected + 1, weapon.current().name);
+            let (x, y) = if second { (panel_x, 295) } else { (10, 55) };
+            ui_textures.push(text_line(font, texture_creator, &weapon_text, white, x, y, 36)?);
+        }
+
+        let hyperspace_key = player_bindings.slots.get(player.slot)
+            .and_then(|bindings| bindings.keys.get(&InputAction::Hyperspace))
+            .map_or("", |key| key.as_str());
+        let hyperspace_text = if player.hyperspace_cooldown > 0.0 {
+            format!("Hyperspace {:.0}s", player.hyperspace_cooldown.ceil())
+        } else {
+            format!("Hyperspace ready ({})", hyperspace_key)
+        };
+        let (x, y) = if second { (panel_x, 340) } else { (10, 145) };
+        ui_textures.push(text_line(font, texture_creator, &hyperspace_text, white, x, y, 36)?);
+
+        let missile_text = format!("Missiles: {}", player.missiles);
+        let (x, y) = if second { (panel_x, 385) } else { (10, 190) };
+        ui_textures.push(text_line(font, texture_creator, &missile_text, white, x, y, 36)?);
+
+        //Active power ups and their remaining time, listed under the health
+        if let Some(power_ups) = power_ups {
+            let mut line_y = if second { 430 } else { 55 };
+            for (name, remaining, color) in [
+                ("Shield", power_ups.shield, Color::RGB(60, 140, 255)),
+                ("Rapid Fire", power_ups.rapid_fire, Color::RGB(255, 210, 40)),
+                ("Spread Shot", power_ups.spread_shot, Color::RGB(230, 80, 230)),
+            ] {
+                if remaining > 0.0 {
+                    let effect_text = format!("{} {:.0}s", name, remaining.ceil());
+                    ui_textures.push(text_line(font, texture_creator, &effect_text, color, (SCREEN_WIDTH - 290) as i32, line_y, 36)?);
+                    line_y += 40;
+                }
+            }
+        }
+    }
+
+    let game_data = ecs.read_storage::<components::GameData>();
+    for game_data in (&game_data).join() {
+        //Score
+        let score_text = "Score: ".to_string() + &game_data.score.to_string();
+        ui_textures.push(text_texture(font, texture_creator, &score_text, white, Rect::new(10i32, 0i32, 140u32, 50u32))?);
+
+        //Level
+        let level_text = "Level: ".to_string() + &game_data.level.to_string();
+        ui_textures.push(text_texture(font, texture_creator, &level_text, white, Rect::new((SCREEN_WIDTH - 140) as i32, 0i32, 110u32, 50u32))?);
+        ui_textures.push(text_line(font, texture_creator, game_data.difficulty.name(), white, 170, 7, 36)?);
+
+        //Time left in time attack, time survived in survival
+        let clock_text = clock_seconds(ecs).map(|seconds| match game_data.mode {
+            GameMode::TimeAttack => format!("Time: {}:{:02}", seconds / 60, seconds % 60),
+            _ => format!("Survived: {}s", seconds),
+        });
+        if let Some(clock_text) = clock_text {
+            //The mothership label and health bar take the top centre, the clock moves down under them
+            let (y, height) = if ecs.read_storage::<components::Boss>().join().next().is_some() { (72, 36) } else { (5, 50) };
+            ui_textures.push(centered_line(font, texture_creator, &clock_text, white, (SCREEN_WIDTH / 2) as i32, y, height)?);
+        }
+
+        //Round wins and kills replace lives in versus
+        if game_data.mode == GameMode::Versus {
+            let wins: Vec<String> = game_data.round_wins.iter().zip(&game_data.kills).enumerate()
+                .map(|(slot, (wins, kills))| format!("P{}: {} wins {} kills", slot + 1, wins, kills))
+                .collect();
+            ui_textures.push(text_line(font, texture_creator, &wins.join("  "), white, 10, 100, 36)?);
+
+            if game_data.round_timer.is_some() {
+                let rounds_to_win = ecs.read_resource::<Tuning>().versus_rounds_to_win;
+                let winner = players.join().next().map(|player| player.slot);
+                let round_text = match winner {
+                    Some(slot) if game_data.round_wins.get(slot).is_some_and(|wins| *wins >= rounds_to_win) => format!("Player {} wins the match!", slot + 1),
+                    Some(slot) => format!("Player {} wins the round", slot + 1),
+                    None => "Draw".to_string(),
+                };
+                ui_textures.push(centered_line(font, texture_creator, &round_text, Color::RGB(255, 210, 40), (SCREEN_WIDTH / 2) as i32, (SCREEN_HEIGHT / 2 - 30) as i32, 70)?);
+            }
+        } else {
+            //Lives counts the ships flying and the spare ones, the weapon line sits between these and the score
+            let lives_text = "Lives: ".to_string() + &(game_data.lives + players.join().count() as u32).to_string();
+            ui_textures.push(text_line(font, texture_creator, &lives_text, white, 10, 100, 36)?);
+
+            //Each player's share of the score in co-op
+            if game_data.player_scores.len() > 1 && game_data.mode != GameMode::Survival {
+                let scores: Vec<String> = game_data.player_scores.iter().enumerate().map(|(slot, score)| format!("P{}: {}", slot + 1, score)).collect();
+                ui_textures.push(text_line(font, texture_creator, &scores.join("  "), white, 10, 235, 36)?);
+            }
+
+            let coop = game_data.respawn_timers.len() > 1;
+            for (slot, timer) in game_data.respawn_timers.iter().enumerate() {
+                if let Some(remaining) = timer {
+                    let respawn_text = if coop {
+                        format!("Player {} respawning in {:.0}", slot + 1, remaining.ceil())
+                    } else {
+                        format!("Respawning in {:.0}", remaining.ceil())
+                    };
+                    let y = (SCREEN_HEIGHT / 2 - 30) as i32 + slot as i32 * 70;
+                    ui_textures.push(centered_line(font, texture_creator, &respawn_text, white, (SCREEN_WIDTH / 2) as i32, y, 60)?);
+                }
+            }
+        }
+
+        //Utils
+        let invinc_text = format!("Press I for player invincibility || Invincibility: {}", game_data.invincible_player);
+        ui_textures.push(text_texture(font, texture_creator, &invinc_text, white, Rect::new((SCREEN_WIDTH / 2 - 300) as i32, (SCREEN_HEIGHT - 100) as i32, 600u32, 65u32))?);
+
+        let fire_mode_text = match ecs.read_resource::<Tuning>().fire_mode {
+            FireMode::Tap => "Press F to toggle fire mode || Fire: Tap",
+            FireMode::Hold => "Press F to toggle fire mode || Fire: Hold",
+        };
+        ui_textures.push(text_line(font, texture_creator, fire_mode_text, white, (SCREEN_WIDTH / 2 - 250) as i32, (SCREEN_HEIGHT - 195) as i32, 40)?);
+
+        let asteroid_text = "Press O to spawn 1K asteroids".to_string();
+        ui_textures.push(text_texture(font, texture_creator, &asteroid_text, white, Rect::new((SCREEN_WIDTH / 2 - 200) as i32, (SCREEN_HEIGHT - 150) as i32, 400u32, 60u32))?);
+    }
+    //Achievement toast
+    if let Some((achievement, _)) = ecs.read_resource::<Achievements>().toasts.front() {
+        let toast_text = format!("Achievement unlocked: {} - {}", achievement.name, achievement.description);
+        ui_textures.push(centered_line(font, texture_creator, &toast_text, Color::RGB(255, 210, 40), (SCREEN_WIDTH / 2) as i32, 110, 40)?);
+    }
+    //Boss name over the health bar drawn by draw_boss_bar
+    if ecs.read_storage::<components::Boss>().join().next().is_some() {
+        ui_textures.push(text_line(font, texture_creator, "MOTHERSHIP", Color::RGB(255, 80, 60), (SCREEN_WIDTH / 2 - 90) as i32, 10, 36)?);
+    }
+    //Total entities
+    {
+        let entity_count = ecs.entities().join().count();
+        let entity_text = "Total Entities: ".to_string() + &entity_count.to_string();
+        ui_textures.push(text_texture(font, texture_creator, &entity_text, Color::RGB(255, 0, 0), Rect::new(10i32, (SCREEN_HEIGHT - 100) as i32, 150u32, 60u32))?);
+    }
+    //fps
+    {
+        let fps_text = "fps: ".to_string() + &fps.to_string();
+        ui_textures.push(text_texture(font, texture_creator, &fps_text, Color::RGB(0, 255, 0), Rect::new((SCREEN_WIDTH - 140) as i32, (SCREEN_HEIGHT - 100) as i32, 90u32, 40u32))?);
+    }
+
+    Ok(ui_textures)
+}
+
+//Title, shop and name entry text, cheap enough to rebuild every frame so typing shows straight away
+pub fn build_menu<'a>(ecs: &World, font: &Font, texture_creator: &'a TextureCreator<WindowContext>) -> Result<Vec<TextureRectTuple<'a>>, String> {
+    let mut ui_textures: Vec<TextureRectTuple> = Vec::new();
+    let white = Color::RGB(255, 255, 255);
+    let highlight = Color::RGB(255, 210, 40);
+    let center_x = (SCREEN_WIDTH / 2) as i32;
+
+    match &*ecs.read_resource::<Screen>() {
+        Screen::Title => {
+            ui_textures.push(centered_line(font, texture_creator, "SPACE SHOOTER", white, center_x, 120, 110)?);
+            let difficulty_text = format!("< Difficulty: {} >", ecs.read_resource::<Tuning>().difficulty.name());
+            ui_textures.push(centered_line(font, texture_creator, &difficulty_text, highlight, center_x, 290, 48)?);
+            let tuning = ecs.read_resource::<Tuning>();
+            let players_text = match (tuning.game_mode, tuning.player_count) {
+                (GameMode::Versus, _) => "Mode: Versus (2 players)".to_string(),
+                (mode, 1) => format!("Mode: {} || < Players: 1 >", mode.name()),
+                (mode, _) => format!("Mode: {} || < Players: 2 (co-op) >", mode.name()),
+            };
+            ui_textures.push(centered_line(font, texture_creator, &players_text, highlight, center_x, 240, 40)?);
+            ui_textures.push(centered_line(font, texture_creator, "Left/Right difficulty || Up/Down players || M mode || Enter to start", white, center_x, 360, 36)?);
+            let unlocked = ecs.read_resource::<Achievements>().unlocked.len();
+            let achievement_text = format!("Achievements: {}/{}", unlocked.min(ALL_ACHIEVEMENTS.len()), ALL_ACHIEVEMENTS.len());
+            ui_textures.push(centered_line(font, texture_creator, &achievement_text, white, center_x, 410, 32)?);
+
+            //Each mode has its own table, versus matches are not scored
+            let table_text = format!("HIGH SCORES - {}", tuning.game_mode.name().to_uppercase());
+            ui_textures.push(centered_line(font, texture_creator, &table_text, white, center_x, 480, 52)?);
+            let high_scores = ecs.read_resource::<HighScores>();
+            if tuning.game_mode == GameMode::Versus {
+                ui_textures.push(centered_line(font, texture_creator, "Versus matches are not scored", white, center_x, 560, 36)?);
+            } else if high_scores.entries_for(tuning.game_mode).next().is_none() {
+                ui_textures.push(centered_line(font, texture_creator, "No scores yet", white, center_x, 560, 36)?);
+            }
+            for (rank, entry) in high_scores.entries_for(tuning.game_mode).enumerate() {
+                let score_text = if entry.mode == GameMode::Survival { format!("{}s", entry.score) } else { entry.score.to_string() };
+                let entry_text = format!("{:>2}. {}  {}  Level {}  {}  {}", rank + 1, entry.name, score_text, entry.level, entry.difficulty.name(), entry.date);
+                ui_textures.push(centered_line(font, texture_creator, &entry_text, white, center_x, 560 + rank as i32 * 42, 34)?);
+            }
+        }
+        Screen::NameEntry(entry) => {
+            ui_textures.push(centered_line(font, texture_creator, "GAME OVER", white, center_x, 200, 110)?);
+            let score_text = format!("New {} high score: {} (Level {}, {})", entry.mode.name(), entry.score, entry.level, entry.difficulty.name());
+            ui_textures.push(centered_line(font, texture_creator, &score_text, highlight, center_x, 380, 48)?);
+            let name_text = format!("Name: {}_", entry.name);
+            ui_textures.push(centered_line(font, texture_creator, &name_text, white, center_x, 480, 60)?);
+            ui_textures.push(centered_line(font, texture_creator, "Type your name and press Enter to save", white, center_x, 590, 36)?);
+        }
+        Screen::Shop { slot, selected } => {
+            let game_data = ecs.read_storage::<components::GameData>();
+            let Some(game_data) = game_data.join().next() else {
+                return Ok(ui_textures);
+            };
+            let title_text = format!("WAVE {} CLEARED", game_data.level);
+            ui_textures.push(centered_line(font, texture_creator, &title_text, white, center_x, 160, 90)?);
+            let credits = game_data.credits.get(*slot).copied().unwrap_or(0);
+            let credits_text = if game_data.credits.len() > 1 {
+                format!("Player {} upgrades || Credits: {}", slot + 1, credits)
+            } else {
+                format!("Upgrades || Credits: {}", credits)
+            };
+            ui_textures.push(centered_line(font, texture_creator, &credits_text, highlight, center_x, 310, 48)?);
+
+            let upgrades = game_data.upgrades.get(*slot).cloned().unwrap_or_default();
+            for (index, upgrade) in ALL_UPGRADES.iter().enumerate() {
+                let cost_text = match upgrades.cost(*upgrade) {
+                    Some(cost) => format!("{} credits", cost),
+                    None => "Maxed".to_string(),
+                };
+                let marker = if index == *selected { ">" } else { " " };
+                let line = format!("{} {}  {}/{}  {}", marker, upgrade.name(), upgrades.rank(*upgrade), MAX_RANK, cost_text);
+                let affordable = upgrades.cost(*upgrade).is_some_and(|cost| cost <= credits);
+                let color = if index == *selected { highlight } else if affordable { white } else { Color::RGB(130, 130, 130) };
+                ui_textures.push(centered_line(font, texture_creator, &line, color, center_x, 420 + index as i32 * 60, 44)?);
+            }
+            ui_textures.push(centered_line(font, texture_creator, "Up/Down choose || Enter buy || C continue", white, center_x, 760, 36)?);
+        }
+        Screen::Playing => {}
+    }
+    Ok(ui_textures)
+}
+
+fn text_texture<'a>(font: &Font, texture_creator: &'a TextureCreator<WindowContext>, text: &str, color: Color, target: Rect) -> Result<TextureRectTuple<'a>, String> {
+    let surface = font.render(text).solid(color).map_err(|e| e.to_string())?;
+    let surface_texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())?;
+    Ok(TextureRectTuple{ texture: surface_texture, rect: target })
+}
+
+//Like text_texture but sized from the font so the text keeps its proportions
+fn text_line<'a>(font: &Font, texture_creator: &'a TextureCreator<WindowContext>, text: &str, color: Color, x: i32, y: i32, height: u32) -> Result<TextureRectTuple<'a>, String> {
+    let (text_width, text_height) = font.size_of(text).map_err(|e| e.to_string())?;
+    let width = (text_width as f64 * height as f64 / text_height.max(1) as f64) as u32;
+    text_texture(font, texture_creator, text, color, Rect::new(x, y, width.max(1), height))
+}
+
+//text_line centred on x
+fn centered_line<'a>(font: &Font, texture_creator: &'a TextureCreator<WindowContext>, text: &str, color: Color, x: i32, y: i32, height: u32) -> Result<TextureRectTuple<'a>, String> {
+    let mut line = text_line(font, texture_creator, text, color, x, y, height)?;
+    line.rect.set_x(x - line.rect.width() as i32 / 2);
+    Ok(line)
+}
+
+const BOSS_BAR_WIDTH: u32 = 800;
+const BOSS_BAR_HEIGHT: u32 = 18;
+
+//Drawn every frame rather than cached with the rest of the HUD so damage shows straight away
+pub fn draw_boss_bar(canvas: &mut WindowCanvas, ecs: &World) -> Result<(), String> {
+    let bosses = ecs.read_storage::<components::Boss>();
+    for boss in (&bosses).join() {
+        let x = (SCREEN_WIDTH / 2 - BOSS_BAR_WIDTH / 2) as i32;
+        let y = 52;
+        let fraction = (boss.health as f64 / boss.max_health.max(1) as f64).clamp(0.0, 1.0);
+
+        canvas.set_draw_color(Color::RGB(60, 20, 20));
+        canvas.fill_rect(Rect::new(x, y, BOSS_BAR_WIDTH, BOSS_BAR_HEIGHT))?;
+        let color = match boss.phase {
+            1 => Color::RGB(230, 60, 40),
+            2 => Color::RGB(255, 140, 30),
+            _ => Color::RGB(255, 230, 60),
+        };
+        canvas.set_draw_color(color);
+        canvas.fill_rect(Rect::new(x, y, ((BOSS_BAR_WIDTH as f64 * fraction) as u32).max(1), BOSS_BAR_HEIGHT))?;
+        canvas.set_draw_color(Color::RGB(255, 255, 255));
+        canvas.draw_rect(Rect::new(x, y, BOSS_BAR_WIDTH, BOSS_BAR_HEIGHT))?;
+    }
+    Ok(())
+}
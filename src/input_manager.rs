@@ -25,4 +25,74 @@ pub fn key_up(input_manager:&mut HashMap<String, bool>, keycode: String) {
 
 pub fn is_key_pressed(input_manager: &HashMap<String, bool>, value: &str) -> bool {
     input_manager.contains_key(&value.to_string()) && input_manager.get(&value.to_string())==Some(&true)
-}
\ No newline at end of file
+}
+
+//Things the player can do, game code asks for these instead of raw keys so controls can be rebound
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputAction {
+    RotateLeft,
+    RotateRight,
+    Thrust,
+    Fire,
+    NextWeapon,
+    Hyperspace,
+    Missile,
+}
+
+//Maps each action to the key name main stores in the input map
+pub struct Bindings {
+    pub keys: HashMap<InputAction, String>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let keys = [
+            (InputAction::RotateLeft, "A"),
+            (InputAction::RotateRight, "D"),
+            (InputAction::Thrust, "W"),
+            (InputAction::Fire, " "),
+            (InputAction::NextWeapon, "Q"),
+            (InputAction::Hyperspace, "H"),
+            (InputAction::Missile, "E"),
+        ];
+        Bindings { keys: keys.iter().map(|(action, key)| (*action, key.to_string())).collect() }
+    }
+}
+
+impl Bindings {
+    //Arrow keys and the right hand modifiers so two players can share the keyboard
+    pub fn player_two() -> Self {
+        let keys = [
+            (InputAction::RotateLeft, "Left"),
+            (InputAction::RotateRight, "Right"),
+            (InputAction::Thrust, "Up"),
+            (InputAction::Fire, "Right Ctrl"),
+            (InputAction::NextWeapon, "Right Shift"),
+            (InputAction::Hyperspace, "Down"),
+            (InputAction::Missile, "Right Alt"),
+        ];
+        Bindings { keys: keys.iter().map(|(action, key)| (*action, key.to_string())).collect() }
+    }
+}
+
+//Bindings for each player slot
+pub struct PlayerBindings {
+    pub slots: Vec<Bindings>,
+}
+
+impl Default for PlayerBindings {
+    fn default() -> Self {
+        PlayerBindings { slots: vec![Bindings::default(), Bindings::player_two()] }
+    }
+}
+
+pub fn is_action_pressed(input_manager: &HashMap<String, bool>, bindings: &Bindings, action: InputAction) -> bool {
+    bindings.keys.get(&action).is_some_and(|key| is_key_pressed(input_manager, key))
+}
+
+//Releases the bound key so one press only triggers the action once
+pub fn release_action(input_manager: &mut HashMap<String, bool>, bindings: &Bindings, action: InputAction) {
+    if let Some(key) = bindings.keys.get(&action) {
+        key_up(input_manager, key.clone());
+    }
+}
@@ -1,71 +1,180 @@
+use std::collections::HashSet;
+
 use specs::prelude::*;
 use specs::{Entities, Join};
 
-use crate::{components};
+use crate::{components, asteroid, damage, game, pickup};
+use crate::components::Faction;
+use crate::camera::Camera;
+use crate::achievements::{GameEvent, GameEvents};
+use crate::tuning::{GameMode, Tuning};
 
-pub struct LaserMovement;
+pub struct LaserDamage;
 
-impl<'a> System<'a> for LaserMovement {
+impl<'a> System<'a> for LaserDamage {
     type SystemData = (
-        WriteStorage<'a, components::Position>,
+        ReadStorage<'a, components::Position>,
         WriteStorage<'a, components::Renderable>,
+        ReadStorage<'a, components::Velocity>,
         WriteStorage<'a, components::Laser>,
+        WriteStorage<'a, components::Asteroid>,
+        WriteStorage<'a, components::Enemy>,
+        WriteStorage<'a, components::GameData>,
         Entities<'a>,
-        Read<'a, crate::DeltaTime>,
+        Read<'a, LazyUpdate>,
+        ReadExpect<'a, Tuning>,
+        Write<'a, GameEvents>,
     );
     fn run (&mut self, data: Self::SystemData) {
-        let (mut positions, mut renderables, laser, entities, delta_time) = data;
+        let (positions, mut renderables, velocities, mut lasers, mut asteroids, mut enemies, mut gamedatas, entities, lazy, tuning, mut events) = data;
+
+        //Deletion only takes effect at the end of the frame, so remember what was already hit
+        let mut destroyed_asteroids = HashSet::new();
+        let mut destroyed_enemies = HashSet::new();
+        //Points earned this frame and the player slot they go to
+        let mut destroyed_sizes = Vec::new();
+        let mut enemy_scores = Vec::new();
 
-        for (position, renderable, laser, entity) in (&mut positions, &mut renderables, &laser, &entities).join() {
-            let radians = position.rot.to_radians();
+        for (laser_pos, laser, laser_entity) in (&positions, &mut lasers, &entities).join() {
+            if laser.faction != Faction::Player {
+                continue;
+            }
+            let mut spent = false;
+            for (asteroid_pos, asteroid_renderable, asteroid_velocity, asteroid, asteroid_entity) in (&positions, &mut renderables, &velocities, &mut asteroids, &entities).join() {
+                if destroyed_asteroids.contains(&asteroid_entity) {
+                    continue;
+                }
+                let diff_x: f64 = (laser_pos.x - asteroid_pos.x).abs();
+                let diff_y: f64 = (laser_pos.y - asteroid_pos.y).abs();
+
+                let hypotenuse: f64 = ((diff_x * diff_x) + (diff_y * diff_y)).sqrt();
 
-            position.x += laser.speed * delta_time.0 * radians.sin();
-            position.y -= laser.speed * delta_time.0 * radians.cos();
+                if hypotenuse < asteroid_renderable.output_width as f64 / 2.0 {
+                    if !laser.hit {
+                        laser.hit = true;
+                        events.push(GameEvent::ShotHit);
+                    }
+                    if asteroid::damage_asteroid(asteroid, asteroid_renderable, 1) {
+                        entities.delete(asteroid_entity).ok();
+                        destroyed_asteroids.insert(asteroid_entity);
+                        destroyed_sizes.push((asteroid.size, laser.owner));
+                        events.push(GameEvent::AsteroidDestroyed);
+                        asteroid::break_asteroid(&lazy, &entities, asteroid_pos, asteroid_velocity, asteroid, laser.owner, &tuning);
+                    }
 
-            if position.x > crate::SCREEN_WIDTH.into() || position.x < 0.0 || position.y > crate::SCREEN_HEIGHT.into() || position.y < 0.0 {
-                entities.delete(entity).ok();
+                    //Piercing lasers carry on through until they run out of hits
+                    if laser.pierce == 0 {
+                        entities.delete(laser_entity).ok();
+                        spent = true;
+                        break;
+                    }
+                    laser.pierce -= 1;
+                }
+            }
+            if spent {
+                continue;
             }
 
-            renderable.img_rotation = position.rot;
+            for (enemy_pos, enemy_renderable, enemy, enemy_entity) in (&positions, &renderables, &mut enemies, &entities).join() {
+                if destroyed_enemies.contains(&enemy_entity) {
+                    continue;
+                }
+                if !within(laser_pos, enemy_pos, enemy_renderable.output_width as f64 / 2.0) {
+                    continue;
+                }
+                if !laser.hit {
+                    laser.hit = true;
+                    events.push(GameEvent::ShotHit);
+                }
+                enemy.health -= 1;
+                if enemy.health < 1 {
+                    entities.delete(enemy_entity).ok();
+                    destroyed_enemies.insert(enemy_entity);
+                    events.push(GameEvent::EnemyDestroyed);
+                    enemy_scores.push((enemy.score_value, laser.owner));
+                    pickup::maybe_drop_pickup(&lazy, &entities, enemy_pos.x, enemy_pos.y, &tuning);
+                }
+
+                if laser.pierce == 0 {
+                    entities.delete(laser_entity).ok();
+                    break;
+                }
+                laser.pierce -= 1;
+            }
+        }
+        if !destroyed_sizes.is_empty() || !enemy_scores.is_empty() {
+            for gamedata in (&mut gamedatas).join() {
+                for (size, owner) in &destroyed_sizes {
+                    gamedata.add_score(*owner, asteroid::score_for_size(*size, gamedata.level));
+                }
+                for (score_value, owner) in &enemy_scores {
+                    gamedata.add_score(*owner, score_value * gamedata.level);
+                }
+            }
         }
     }
 }
 
-pub struct LaserDamage;
+//Enemy fire hitting players, and in versus each ship's fire hitting the other
+pub struct EnemyLaserDamage;
 
-impl<'a> System<'a> for LaserDamage {
+impl<'a> System<'a> for EnemyLaserDamage {
     type SystemData = (
-        WriteStorage<'a, components::Position>,
-        WriteStorage<'a, components::Renderable>,
-        WriteStorage<'a, components::Laser>,
-        WriteStorage<'a, components::Asteroid>,
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::Renderable>,
+        ReadStorage<'a, components::Laser>,
+        WriteStorage<'a, components::Player>,
+        ReadStorage<'a, components::PowerUps>,
+        WriteStorage<'a, components::Velocity>,
         WriteStorage<'a, components::GameData>,
-        Entities<'a>
+        Entities<'a>,
+        Write<'a, Camera>,
+        ReadExpect<'a, Tuning>,
+        Write<'a, GameEvents>,
     );
     fn run (&mut self, data: Self::SystemData) {
-        let (positions, renderables, lasers, asteroids, _ ,  entities) = &data;
-
-        let mut should_add_score = false;
+        let (positions, renderables, lasers, mut players, power_ups, mut velocities, mut game_data, entities, mut camera, tuning, mut events) = data;
 
-        for (laser_pos,_ ,_, laser_entity) in (positions, renderables, lasers, entities).join() {
-            for (asteroid_pos, asteroid_renderable, _, asteroid_entity) in (positions, renderables, asteroids, entities).join() {
-                let diff_x: f64 = (laser_pos.x - asteroid_pos.x).abs();
-                let diff_y: f64 = (laser_pos.y - asteroid_pos.y).abs();
-
-                let hypotenuse: f64 = ((diff_x * diff_x) + (diff_y * diff_y)).sqrt();
+        if (&game_data).join().any(|data| data.invincible_player) {
+            return;
+        }
+        let versus = (&game_data).join().any(|data| data.mode == GameMode::Versus);
 
-                if hypotenuse < asteroid_renderable.output_width as f64 / 2.0 {
+        //Player slots that got a kill this frame
+        let mut kills = Vec::new();
+        for (laser_pos, laser, laser_entity) in (&positions, &lasers, &entities).join() {
+            if laser.faction != Faction::Enemy && !versus {
+                continue;
+            }
+            for (player_pos, player_renderable, player, power_ups, velocity, player_entity) in (&positions, &renderables, &mut players, power_ups.maybe(), (&mut velocities).maybe(), &entities).join() {
+                //Ships never shoot themselves
+                if laser.faction == Faction::Player && laser.owner == Some(player.slot) {
+                    continue;
+                }
+                if within(laser_pos, player_pos, player_renderable.output_width as f64 / 2.0) {
+                    let shielded = power_ups.is_some_and(|power_ups| power_ups.shield > 0.0);
+                    let target = damage::PlayerTarget { player: &mut *player, entity: player_entity, pos: player_pos, velocity, shielded };
+                    let landed = damage::damage_player(target, (laser_pos.x, laser_pos.y), &entities, &mut camera, &tuning, &mut events);
+                    if landed && player.health < 1 {
+                        if let (Faction::Player, Some(shooter)) = (laser.faction, laser.owner) {
+                            kills.push(shooter);
+                        }
+                    }
                     entities.delete(laser_entity).ok();
-                    entities.delete(asteroid_entity).ok();
-                    should_add_score = true;
+                    break;
                 }
             }
         }
-        if should_add_score {
-            let (_, _, _, _, mut gamedatas, _) = data;
-            for gamedata in (&mut gamedatas).join() {
-                gamedata.score += 10 * gamedata.level;
+        for data in (&mut game_data).join() {
+            for shooter in &kills {
+                game::credit_kill(data, *shooter);
             }
         }
     }
-}
\ No newline at end of file
+}
+
+fn within(a: &components::Position, b: &components::Position, radius: f64) -> bool {
+    let diff_x = a.x - b.x;
+    let diff_y = a.y - b.y;
+    diff_x * diff_x + diff_y * diff_y < radius * radius
+}
@@ -0,0 +1,24 @@
+use specs::prelude::*;
+use specs::{Entities, Join};
+
+use crate::components;
+
+pub struct LifetimeExpiry;
+
+impl<'a> System<'a> for LifetimeExpiry {
+    type SystemData = (
+        WriteStorage<'a, components::Lifetime>,
+        Entities<'a>,
+        Read<'a, crate::DeltaTime>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (mut lifetimes, entities, delta_time) = data;
+
+        for (lifetime, entity) in (&mut lifetimes, &entities).join() {
+            lifetime.remaining -= delta_time.0;
+            if lifetime.remaining <= 0.0 {
+                entities.delete(entity).ok();
+            }
+        }
+    }
+}
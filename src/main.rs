@@ -2,30 +2,48 @@ use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
-use sdl2::render::{WindowCanvas, Texture};
+use sdl2::render::WindowCanvas;
 use specs::{World, WorldExt, Join, DispatcherBuilder};
 
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
 use texture_manager::TextureManager;
+use hud::TextureRectTuple;
 
 pub mod input_manager;
 pub mod components;
 pub mod game;
 pub mod asteroid;
 pub mod laser;
+pub mod movement;
+pub mod boundary;
+pub mod lifetime;
+pub mod tuning;
+pub mod camera;
+pub mod pickup;
+pub mod weapon;
+pub mod enemy;
+pub mod damage;
+pub mod boss;
+pub mod waves;
+pub mod hud;
+pub mod highscores;
+pub mod achievements;
+pub mod missile;
+pub mod explosion;
+pub mod gravity;
+pub mod shop;
 pub mod texture_manager;
 
 const SCREEN_WIDTH: u32 = 1920;
 const SCREEN_HEIGHT: u32 = 1080;
 
-struct State { ecs: World }
+//Size of the playfield, the camera shows a screen sized window of it
+const WORLD_WIDTH: u32 = SCREEN_WIDTH * 3;
+const WORLD_HEIGHT: u32 = SCREEN_HEIGHT * 3;
 
-struct TextureRectTuple<'a> {
-    texture: Texture<'a>,
-    rect: Rect,
-}
+struct State { ecs: World }
 
 #[derive(Default)]
 pub struct DeltaTime(f64);
@@ -48,34 +66,134 @@ fn main() -> Result<(), String> {
     //Load and add these to texture manager
     let mut texture_manager = TextureManager::new();
     texture_manager.add_texture("Assets/Images/rocket.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/rocket_2.png".to_string(), &texture_creator)?;
     texture_manager.add_texture("Assets/Images/asteroid_1.png".to_string(), &texture_creator)?;
     texture_manager.add_texture("Assets/Images/asteroid_2.png".to_string(), &texture_creator)?;
     texture_manager.add_texture("Assets/Images/asteroid_3.png".to_string(), &texture_creator)?;
     texture_manager.add_texture("Assets/Images/laser.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/enemy_laser.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/ufo.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/boss_core.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/boss_weak_point.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/boss_armour.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/hyperspace.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/missile.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/explosion.png".to_string(), &texture_creator)?;
+    texture_manager.add_texture("Assets/Images/black_hole.png".to_string(), &texture_creator)?;
+    for texture in asteroid::VARIANT_TEXTURES {
+        texture_manager.add_texture(texture.to_string(), &texture_creator)?;
+    }
+    for kind in pickup::ALL_PICKUPS {
+        texture_manager.add_texture(pickup::texture_name(kind).to_string(), &texture_creator)?;
+    }
 
     let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
     let font = ttf_context.load_font(&"Assets/Fonts/Orbitron-Regular.ttf", 100)?;
     
     let mut event_pump = sdl_context.event_pump()?;
+    let text_input = video_subsystem.text_input();
+    text_input.stop();
     let mut input_manager: HashMap<String, bool> = HashMap::new();
 
     //ecs component registration
     let mut game_state = State { ecs: World::new() };
     game_state.ecs.register::<components::Position>();
     game_state.ecs.register::<components::Renderable>();
+    game_state.ecs.register::<components::Velocity>();
+    game_state.ecs.register::<components::Acceleration>();
+    game_state.ecs.register::<components::AngularVelocity>();
+    game_state.ecs.register::<components::Drag>();
+    game_state.ecs.register::<components::SpeedLimit>();
+    game_state.ecs.register::<components::BoundaryBehavior>();
+    game_state.ecs.register::<components::Lifetime>();
     game_state.ecs.register::<components::Player>();
     game_state.ecs.register::<components::Asteroid>();
     game_state.ecs.register::<components::Laser>();
     game_state.ecs.register::<components::GameData>();
+    game_state.ecs.register::<components::Pickup>();
+    game_state.ecs.register::<components::PowerUps>();
+    game_state.ecs.register::<components::Weapon>();
+    game_state.ecs.register::<components::Enemy>();
+    game_state.ecs.register::<components::Boss>();
+    game_state.ecs.register::<components::BossPart>();
+    game_state.ecs.register::<components::Missile>();
+    game_state.ecs.register::<components::Explosion>();
+    game_state.ecs.register::<components::GravityWell>();
 
     let mut dispatcher = DispatcherBuilder::new()
-        .with(asteroid::AsteroidMovement, "asteroid_movement", &[])
-        .with(asteroid::AsteroidCollider, "asteroid_collider", &[])
-        .with(laser::LaserMovement, "laser_movement", &[])
-        .with(laser::LaserDamage, "laser_damage", &[])
+        .with(movement::Movement, "movement", &[])
+        .with(boundary::Boundary, "boundary", &["movement"])
+        .with(camera::CameraFollow, "camera_follow", &["boundary"])
+        .with(lifetime::LifetimeExpiry, "lifetime_expiry", &[])
+        .with(asteroid::AsteroidMovement, "asteroid_movement", &["boundary"])
+        .with(asteroid::AsteroidCollider, "asteroid_collider", &["asteroid_movement"])
+        .with(asteroid::MagneticPull, "magnetic_pull", &["movement"])
+        .with(gravity::Gravity, "gravity", &["boundary", "magnetic_pull"])
+        .with(enemy::EnemyAi, "enemy_ai", &[])
+        .with(weapon::WeaponSystem, "weapon", &["enemy_ai"])
+        .with(laser::LaserDamage, "laser_damage", &["boundary"])
+        .with(laser::EnemyLaserDamage, "enemy_laser_damage", &["boundary", "asteroid_collider"])
+        .with(boss::BossAi, "boss_ai", &[])
+        .with(boss::BossPartFollow, "boss_part_follow", &["boundary"])
+        .with(boss::BossDamage, "boss_damage", &["boss_part_follow", "laser_damage"])
+        .with(boss::BossCollider, "boss_collider", &["boss_part_follow", "enemy_laser_damage"])
+        .with(pickup::PickupCollector, "pickup_collector", &["boundary"])
+        .with(pickup::PowerUpTimers, "power_up_timers", &["pickup_collector", "asteroid_collider"])
+        .with(missile::MissileGuidance, "missile_guidance", &["boundary"])
+        .with(missile::MissileImpact, "missile_impact", &["missile_guidance"])
+        .with(explosion::ExplosionDamage, "explosion_damage", &["laser_damage"])
+        .with(damage::Invulnerability, "invulnerability", &["boss_collider"])
+        .with(achievements::AchievementTracker, "achievement_tracker", &["weapon", "laser_damage", "enemy_laser_damage", "boss_damage", "boss_collider", "explosion_damage"])
         .build();
 
     game_state.ecs.insert(DeltaTime(0.0));
+    game_state.ecs.insert(camera::Starfield::new(1500));
+    game_state.ecs.insert(input_manager::PlayerBindings::default());
+    game_state.ecs.insert(waves::load_waves(waves::WAVE_FILE)?);
+    game_state.ecs.insert(waves::WaveSpawner::default());
+    game_state.ecs.insert(game::Screen::default());
+    game_state.ecs.insert(highscores::HighScores::load(highscores::HIGH_SCORE_FILE));
+    game_state.ecs.insert(achievements::GameEvents::default());
+    game_state.ecs.insert(achievements::Achievements::load(achievements::ACHIEVEMENT_FILE));
+
+    //Pass --classic to wrap everything around the screen like the arcade original
+    let mut tuning = if std::env::args().any(|arg| arg == "--classic") {
+        tuning::Tuning::classic()
+    } else {
+        tuning::Tuning::default()
+    };
+    //Pass --autofire to keep shooting while fire is held, F toggles it in game
+    if std::env::args().any(|arg| arg == "--autofire") {
+        tuning.fire_mode = tuning::FireMode::Hold;
+    }
+    //Pass --difficulty=easy|normal|hard|insane to pick how hard the run is
+    for arg in std::env::args() {
+        if let Some(name) = arg.strip_prefix("--difficulty=") {
+            tuning.difficulty = tuning::Difficulty::from_name(name).ok_or(format!("Unknown difficulty '{}'", name))?;
+        }
+    }
+    //Pass --coop for a second player on the arrow keys
+    if std::env::args().any(|arg| arg == "--coop") {
+        tuning.player_count = 2;
+    }
+    //Pass --versus for a two player arena match
+    if std::env::args().any(|arg| arg == "--versus") {
+        tuning.game_mode = tuning::GameMode::Versus;
+    }
+    //Pass --mode=<name> to start on another mode, e.g. --mode=survival
+    for arg in std::env::args() {
+        if let Some(name) = arg.strip_prefix("--mode=") {
+            tuning.game_mode = tuning::GameMode::from_name(name).ok_or(format!("Unknown game mode '{}'", name))?;
+        }
+    }
+    //Pass --reduced-motion to disable screen shake, zoom and kick
+    if std::env::args().any(|arg| arg == "--reduced-motion") {
+        tuning.camera_effects = false;
+    }
+    let mut camera = camera::Camera::default();
+    camera.effects_enabled = tuning.camera_effects;
+    game_state.ecs.insert(camera);
+    game_state.ecs.insert(tuning);
 
     let mut frame_count = 0u64;
     let mut last_frame_time = Instant::now();
@@ -86,6 +204,8 @@ fn main() -> Result<(), String> {
     let mut loop_count = 100;
 
     let mut ui_textures: Vec<TextureRectTuple> = Vec::new();
+    //Second the HUD clock showed when it was last built
+    let mut shown_clock = None;
 
     'running:loop {
         for event in event_pump.poll_iter() {
@@ -98,6 +218,10 @@ fn main() -> Result<(), String> {
                 Event::KeyDown {keycode: Some(Keycode::Escape), .. } => {
                     break 'running;
                 },
+                //Name entry on the game over screen
+                Event::TextInput {text, .. } => {
+                    game::text_input(&mut game_state.ecs, &text)
+                },
                 //Shooting
                 Event::KeyDown {keycode: Some(Keycode::Space), .. } => {
                     input_manager::key_down(&mut input_manager, " ".to_string())
@@ -157,95 +281,44 @@ fn main() -> Result<(), String> {
         // Update DeltaTime resource with the new value
         game_state.ecs.write_resource::<DeltaTime>().0 = delta_time;
         game::update(&mut game_state.ecs, &mut input_manager, delta_time);
-        dispatcher.dispatch(&game_state.ecs);
+        let playing = matches!(*game_state.ecs.read_resource::<game::Screen>(), game::Screen::Playing);
+        if playing {
+            dispatcher.dispatch(&game_state.ecs);
+        }
         game_state.ecs.maintain();
 
-        if loop_count < 100
+        //SDL only sends text events while text input is on
+        let entering_name = matches!(*game_state.ecs.read_resource::<game::Screen>(), game::Screen::NameEntry(_));
+        if entering_name != text_input.is_active() {
+            if entering_name { text_input.start() } else { text_input.stop() }
+        }
+
+        //Show and clear achievement toasts straight away instead of waiting for the next HUD rebuild
+        if std::mem::take(&mut game_state.ecs.write_resource::<achievements::Achievements>().toast_changed) {
+            loop_count = 100;
+        }
+        //Same for the time attack and survival clock, which would otherwise jump several seconds at a time
+        let clock = hud::clock_seconds(&game_state.ecs);
+        if clock != shown_clock {
+            shown_clock = clock;
+            loop_count = 100;
+        }
+
+        if !playing
+        {
+            //Rebuild the HUD as soon as a run starts
+            loop_count = 100;
+            ui_textures = hud::build_menu(&game_state.ecs, &font, &texture_creator)?;
+            render(&mut canvas, &mut texture_manager, &game_state.ecs, &mut ui_textures)?;
+        }
+        else if loop_count < 100
         {
             render(&mut canvas,  &mut texture_manager,  &game_state.ecs,  &mut ui_textures)?;
         }
         else
         {
             loop_count = 0;
-            ui_textures.clear();
-
-            //Health
-            let players = game_state.ecs.read_storage::<components::Player>();
-            for player in (&players).join() {
-                let health_text = "Health: ".to_string() + &player.health.to_string();
-
-                let target = Rect::new((SCREEN_WIDTH - 290) as i32, 0i32, 110u32, 50u32);
-                let surface = font.render(&health_text).solid(Color::RGB(255, 255, 255)).map_err(|e| e.to_string())?;
-                let surface_texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())?;
-
-                let health_tuple = TextureRectTuple{ texture: (surface_texture), rect: (target) };
-                ui_textures.push(health_tuple);
-            }
-
-            let game_data = game_state.ecs.read_storage::<components::GameData>();
-            for game_data in (&game_data).join() {
-                //Score
-                let score_text = "Score: ".to_string() + &game_data.score.to_string();
-
-                let target = Rect::new(10i32, 0i32, 140u32, 50u32);
-                let surface = font.render(&score_text).solid(Color::RGB(255, 255, 255)).map_err(|e| e.to_string())?;
-                let surface_texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())?;
-                let score_tuple = TextureRectTuple{ texture: (surface_texture), rect: (target) };
-
-                ui_textures.push(score_tuple);
-
-                //Level
-                let level_text = "Level: ".to_string() + &game_data.level.to_string();
-
-                let target = Rect::new((crate::SCREEN_WIDTH - 140) as i32, 0i32, 110u32, 50u32);
-                let surface = font.render(&level_text).solid(Color::RGB(255, 255, 255)).map_err(|e| e.to_string())?;
-                let surface_texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())?;
-                let level_tuple = TextureRectTuple{ texture: (surface_texture), rect: (target) };
-
-                ui_textures.push(level_tuple);
-
-                //Utils
-                let invinc_text = format!("Press I for player invincibility || Invincibility: {}", game_data.invincible_player);
-
-                let target = Rect::new((SCREEN_WIDTH / 2 - 300) as i32, (SCREEN_HEIGHT - 100) as i32, 600u32, 65u32);
-                let surface = font.render(&invinc_text).solid(Color::RGB(255, 255, 255)).map_err(|e| e.to_string())?;
-                let surface_texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())?;
-                let invinc_tuple = TextureRectTuple{ texture: (surface_texture), rect: (target) };
-
-                ui_textures.push(invinc_tuple);
-
-                let asteroid_text = "Press O to spawn 1K asteroids".to_string();
-
-                let target = Rect::new((SCREEN_WIDTH / 2 - 200) as i32, (SCREEN_HEIGHT - 150) as i32, 400u32, 60u32);
-                let surface = font.render(&asteroid_text).solid(Color::RGB(255, 255, 255)).map_err(|e| e.to_string())?;
-                let surface_texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())?;
-                let asteroid_tuple = TextureRectTuple{ texture: (surface_texture), rect: (target) };
-
-                ui_textures.push(asteroid_tuple);
-            }
-            //Total entities
-            {
-                let entity_count = game_state.ecs.entities().join().count();
-                let entity_text = "Total Entities: ".to_string() + &entity_count.to_string();
-
-                let target = Rect::new(10i32, (SCREEN_HEIGHT - 100) as i32, 150u32, 60u32);
-                let surface = font.render(&entity_text).solid(Color::RGB(255, 0, 0)).map_err(|e| e.to_string())?;
-                let surface_texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())?;
-                let entity_tuple = TextureRectTuple{ texture: (surface_texture), rect: (target) };
-
-                ui_textures.push(entity_tuple);
-            }
-            //fps
-            {
-                let fps_text = "fps: ".to_string() + &fps.to_string();
-
-                let target = Rect::new((SCREEN_WIDTH - 140) as i32, (SCREEN_HEIGHT - 100) as i32, 90u32, 40u32);
-                let surface = font.render(&fps_text).solid(Color::RGB(0, 255, 0)).map_err(|e| e.to_string())?;
-                let surface_texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())?;
-                let fps_tuple = TextureRectTuple{ texture: (surface_texture), rect: (target) };
-
-                ui_textures.push(fps_tuple);
-            }
+            ui_textures = hud::build_hud(&game_state.ecs, &font, &texture_creator, fps)?;
 
             render(&mut canvas, &mut texture_manager, &game_state.ecs, &mut ui_textures)?;
         }
@@ -259,21 +332,49 @@ fn render (canvas: &mut WindowCanvas, texture_manager: &mut TextureManager, ecs:
     canvas.set_draw_color(color);
     canvas.clear();
 
+    let camera = ecs.read_resource::<camera::Camera>();
+
+    canvas.set_draw_color(Color::RGB(120, 120, 120));
+    for (star_x, star_y) in &ecs.read_resource::<camera::Starfield>().stars {
+        let (x, y) = camera.world_to_screen(*star_x, *star_y);
+        if x >= 0.0 && y >= 0.0 && x < SCREEN_WIDTH as f64 && y < SCREEN_HEIGHT as f64 {
+            canvas.draw_point(Point::new(x as i32, y as i32))?;
+        }
+    }
+
+    //Edge of the world
+    let (world_x, world_y) = camera.world_to_screen(0.0, 0.0);
+    let (world_right, world_bottom) = camera.world_to_screen(WORLD_WIDTH as f64, WORLD_HEIGHT as f64);
+    canvas.set_draw_color(Color::RGB(60, 60, 90));
+    canvas.draw_rect(Rect::new(world_x as i32, world_y as i32, (world_right - world_x) as u32, (world_bottom - world_y) as u32))?;
+
     let positions = ecs.read_storage::<components::Position>();
     let renderables = ecs.read_storage::<components::Renderable>();
 
     for (renderable, pos) in (&renderables, &positions).join() {
+        if !renderable.visible {
+            continue;
+        }
+        let (screen_x, screen_y) = camera.world_to_screen(pos.x, pos.y);
+        let x: i32 = screen_x as i32;
+        let y: i32 = screen_y as i32;
+        let output_width = ((renderable.output_width as f64 * camera.zoom()) as u32).max(1);
+        let output_height = ((renderable.output_height as f64 * camera.zoom()) as u32).max(1);
+
+        //Skip anything entirely outside the view
+        let half_width = (output_width / 2) as i32;
+        let half_height = (output_height / 2) as i32;
+        if x + half_width < 0 || y + half_height < 0 || x - half_width > SCREEN_WIDTH as i32 || y - half_height > SCREEN_HEIGHT as i32 {
+            continue;
+        }
 
         let src = Rect::new(0, 0, renderable.img_width, renderable.img_height);
-        let x: i32 = pos.x as i32;
-        let y: i32 = pos.y as i32;
-
-        let dest = Rect::new(x - ((renderable.output_width / 2) as i32), y - ((renderable.output_height / 2) as i32), renderable.output_width, renderable.output_height);
-        let center = Point::new((renderable.output_width / 2) as i32, (renderable.output_height / 2) as i32);
+        let dest = Rect::new(x - half_width, y - half_height, output_width, output_height);
+        let center = Point::new(half_width, half_height);
 
         let texture = texture_manager.get_texture(&renderable.texture_name).ok_or("Texture not found")?;
         canvas.copy_ex(
-            &texture,
+            texture,
             src,
             dest,
             renderable.img_rotation,
@@ -283,6 +384,8 @@ fn render (canvas: &mut WindowCanvas, texture_manager: &mut TextureManager, ecs:
         )?;
     }
 
+    hud::draw_boss_bar(canvas, ecs)?;
+
     for texture_rect in ui_textures {
         canvas.copy(&texture_rect.texture, None, texture_rect.rect)?;
     }
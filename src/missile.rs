@@ -0,0 +1,134 @@
+use specs::prelude::*;
+use specs::{Entities, Join};
+
+use crate::{components, explosion, movement};
+
+//Missiles each ship carries into a wave
+pub const MISSILE_AMMO: u32 = 4;
+const MISSILE_SPEED: f64 = 450.0;
+//Degrees per second the missile can turn towards its target
+const MISSILE_TURN_RATE: f64 = 180.0;
+//Targets are only picked up ahead of the missile, within this many degrees either side of its heading
+const SEEK_HALF_ANGLE: f64 = 45.0;
+const SEEK_RANGE: f64 = 1000.0;
+const MISSILE_LIFETIME: f64 = 5.0;
+const BLAST_RADIUS: f64 = 140.0;
+const BLAST_DAMAGE: i32 = 3;
+
+pub fn build_missile<B: Builder>(builder: B, origin: &components::Position, owner: Option<usize>) -> B {
+    builder
+        .with(components::Position { x: origin.x, y: origin.y, rot: origin.rot })
+        .with(components::Renderable {
+            texture_name: String::from("Assets/Images/missile.png"),
+            img_width: 32,
+            img_height: 96,
+            output_width: 16,
+            output_height: 48,
+            img_rotation: origin.rot,
+            visible: true
+        })
+        .with(components::Missile { target: None, owner })
+        .with(movement::velocity_from_heading(origin.rot, MISSILE_SPEED))
+        .with(components::BoundaryBehavior::Despawn)
+        .with(components::Lifetime { remaining: MISSILE_LIFETIME })
+}
+
+//Picks a target for every missile that has none and turns it towards it.
+//Boss armour is not worth a missile, only weak points and the exposed core are chased
+pub struct MissileGuidance;
+
+impl<'a> System<'a> for MissileGuidance {
+    type SystemData = (
+        WriteStorage<'a, components::Position>,
+        WriteStorage<'a, components::Velocity>,
+        WriteStorage<'a, components::Missile>,
+        ReadStorage<'a, components::Asteroid>,
+        ReadStorage<'a, components::Enemy>,
+        ReadStorage<'a, components::Boss>,
+        ReadStorage<'a, components::BossPart>,
+        Entities<'a>,
+        Read<'a, crate::DeltaTime>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (mut positions, mut velocities, mut missiles, asteroids, enemies, bosses, parts, entities, delta_time) = data;
+
+        let mut targets: Vec<(Entity, f64, f64)> = (&positions, &entities, asteroids.mask() | enemies.mask()).join()
+            .map(|(pos, entity, _)| (entity, pos.x, pos.y))
+            .collect();
+        targets.extend((&positions, &entities, &parts).join()
+            .filter(|(_, _, part)| part.weak_point)
+            .map(|(pos, entity, _)| (entity, pos.x, pos.y)));
+        targets.extend((&positions, &entities, &bosses).join()
+            .filter(|(_, _, boss)| boss.phase >= 3)
+            .map(|(pos, entity, _)| (entity, pos.x, pos.y)));
+
+        for (pos, velocity, missile) in (&mut positions, &mut velocities, &mut missiles).join() {
+            //Whatever it was chasing was destroyed first, look for something else
+            let current = missile.target.and_then(|target| targets.iter().find(|(entity, _, _)| *entity == target));
+            let target = match current {
+                Some(target) => Some(*target),
+                None => acquire_target(pos, &targets),
+            };
+            missile.target = target.map(|(entity, _, _)| entity);
+
+            if let Some((_, target_x, target_y)) = target {
+                let turn = angle_between(pos.rot, movement::rotation_of(target_x - pos.x, target_y - pos.y));
+                let max_turn = MISSILE_TURN_RATE * delta_time.0;
+                pos.rot = (pos.rot + turn.clamp(-max_turn, max_turn)).rem_euclid(360.0);
+                *velocity = movement::velocity_from_heading(pos.rot, MISSILE_SPEED);
+            }
+        }
+    }
+}
+
+//Nearest target within range inside the seeking cone
+fn acquire_target(pos: &components::Position, targets: &[(Entity, f64, f64)]) -> Option<(Entity, f64, f64)> {
+    targets.iter()
+        .filter(|(_, x, y)| {
+            let (diff_x, diff_y) = (x - pos.x, y - pos.y);
+            diff_x * diff_x + diff_y * diff_y < SEEK_RANGE * SEEK_RANGE
+                && angle_between(pos.rot, movement::rotation_of(diff_x, diff_y)).abs() <= SEEK_HALF_ANGLE
+        })
+        .min_by(|a, b| distance_squared(pos, a.1, a.2).total_cmp(&distance_squared(pos, b.1, b.2)))
+        .copied()
+}
+
+//Signed shortest turn in degrees from one rotation to another
+fn angle_between(from: f64, to: f64) -> f64 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn distance_squared(pos: &components::Position, x: f64, y: f64) -> f64 {
+    (x - pos.x) * (x - pos.x) + (y - pos.y) * (y - pos.y)
+}
+
+//Detonates missiles that touch an asteroid, enemy or any part of a boss
+pub struct MissileImpact;
+
+impl<'a> System<'a> for MissileImpact {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::Renderable>,
+        ReadStorage<'a, components::Missile>,
+        ReadStorage<'a, components::Asteroid>,
+        ReadStorage<'a, components::Enemy>,
+        ReadStorage<'a, components::Boss>,
+        ReadStorage<'a, components::BossPart>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, renderables, missiles, asteroids, enemies, bosses, parts, entities, lazy) = data;
+
+        for (missile_pos, missile, missile_entity) in (&positions, &missiles, &entities).join() {
+            let hit = (&positions, &renderables, asteroids.mask() | enemies.mask() | bosses.mask() | parts.mask()).join().any(|(target_pos, target_renderable, _)| {
+                let radius = target_renderable.output_width as f64 / 2.0;
+                distance_squared(missile_pos, target_pos.x, target_pos.y) < radius * radius
+            });
+            if hit {
+                entities.delete(missile_entity).ok();
+                explosion::build_explosion(lazy.create_entity(&entities), missile_pos.x, missile_pos.y, BLAST_RADIUS, BLAST_DAMAGE, missile.owner, false).build();
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use specs::prelude::*;
+use specs::Join;
+
+use crate::components;
+
+//Frame rate the original per-frame tuning values were balanced around
+pub const REFERENCE_FRAME_RATE: f64 = 60.0;
+
+pub struct Movement;
+
+impl<'a> System<'a> for Movement {
+    type SystemData = (
+        WriteStorage<'a, components::Position>,
+        WriteStorage<'a, components::Velocity>,
+        WriteStorage<'a, components::Acceleration>,
+        ReadStorage<'a, components::AngularVelocity>,
+        ReadStorage<'a, components::Drag>,
+        ReadStorage<'a, components::SpeedLimit>,
+        ReadStorage<'a, components::Player>,
+        WriteStorage<'a, components::Renderable>,
+        Read<'a, crate::DeltaTime>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (mut positions, mut velocities, mut accelerations, angular_velocities, drags, speed_limits, players, mut renderables, delta_time) = data;
+        let delta_time = delta_time.0;
+
+        for (pos, velocity, acceleration, drag, limit, player) in (&mut positions, &mut velocities, accelerations.maybe(), drags.maybe(), speed_limits.maybe(), players.maybe()).join() {
+            let (acc_x, acc_y) = acceleration.map_or((0.0, 0.0), |acc| (acc.x, acc.y));
+            let damping = drag.map_or(0.0, |drag| drag.damping);
+
+            let (mut new_vel_x, mut dist_x) = integrate_axis(velocity.x, acc_x, damping, delta_time);
+            let (mut new_vel_y, mut dist_y) = integrate_axis(velocity.y, acc_y, damping, delta_time);
+
+            if let Some(limit) = limit {
+                //The knockback from a hit may throw a ship faster than it can fly. Until the hit wears off
+                //it keeps the speed it already has, but thrust can never take it any faster
+                let knocked_back = player.is_some_and(|player| player.invulnerable > 0.0);
+                let max_speed = if knocked_back {
+                    limit.max_speed.max((velocity.x * velocity.x + velocity.y * velocity.y).sqrt())
+                } else {
+                    limit.max_speed
+                };
+                let speed = (new_vel_x * new_vel_x + new_vel_y * new_vel_y).sqrt();
+                if speed > max_speed {
+                    new_vel_x = new_vel_x / speed * max_speed;
+                    new_vel_y = new_vel_y / speed * max_speed;
+
+                    //Once capped the analytic distance overshoots, average the two velocities instead
+                    dist_x = (velocity.x + new_vel_x) * 0.5 * delta_time;
+                    dist_y = (velocity.y + new_vel_y) * 0.5 * delta_time;
+                }
+            }
+
+            pos.x += dist_x;
+            pos.y += dist_y;
+            velocity.x = new_vel_x;
+            velocity.y = new_vel_y;
+        }
+
+        for acceleration in (&mut accelerations).join() {
+            acceleration.x = 0.0;
+            acceleration.y = 0.0;
+        }
+
+        for (pos, angular_velocity) in (&mut positions, &angular_velocities).join() {
+            pos.rot += angular_velocity.speed * delta_time;
+
+            //Keep rotation within 0..360 degrees
+            pos.rot = pos.rot.rem_euclid(360.0);
+        }
+
+        for (pos, renderable) in (&positions, &mut renderables).join() {
+            renderable.img_rotation = pos.rot;
+        }
+    }
+}
+
+//Solves dv/dt = acceleration - damping * v exactly over delta_time,
+//returning the new velocity and the distance travelled
+fn integrate_axis(velocity: f64, acceleration: f64, damping: f64, delta_time: f64) -> (f64, f64) {
+    if damping <= f64::EPSILON {
+        let new_velocity = velocity + acceleration * delta_time;
+        let distance = velocity * delta_time + 0.5 * acceleration * delta_time * delta_time;
+        return (new_velocity, distance);
+    }
+
+    let terminal_velocity = acceleration / damping;
+    let decay = (-damping * delta_time).exp();
+
+    let new_velocity = terminal_velocity + (velocity - terminal_velocity) * decay;
+    let distance = terminal_velocity * delta_time + (velocity - terminal_velocity) * (1.0 - decay) / damping;
+    (new_velocity, distance)
+}
+
+//Unit vector pointing along a rotation in degrees, 0 being straight up the screen
+pub fn heading(rot: f64) -> (f64, f64) {
+    let radians = rot.to_radians();
+    (radians.sin(), -radians.cos())
+}
+
+pub fn velocity_from_heading(rot: f64, speed: f64) -> components::Velocity {
+    let (dir_x, dir_y) = heading(rot);
+    components::Velocity { x: dir_x * speed, y: dir_y * speed }
+}
+
+//Inverse of heading, rotation in degrees of a direction vector
+pub fn rotation_of(x: f64, y: f64) -> f64 {
+    x.atan2(-y).to_degrees().rem_euclid(360.0)
+}
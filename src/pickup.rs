@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+use specs::prelude::*;
+use specs::{Entities, Join};
+
+use crate::components;
+use crate::components::PickupKind;
+use crate::tuning::Tuning;
+
+pub const ALL_PICKUPS: [PickupKind; 5] = [
+    PickupKind::Repair,
+    PickupKind::Shield,
+    PickupKind::RapidFire,
+    PickupKind::SpreadShot,
+    PickupKind::ScoreBonus,
+];
+
+const PICKUP_SIZE: u32 = 40;
+const SCORE_BONUS: u32 = 100;
+
+pub fn texture_name(kind: PickupKind) -> &'static str {
+    match kind {
+        PickupKind::Repair => "Assets/Images/pickup_repair.png",
+        PickupKind::Shield => "Assets/Images/pickup_shield.png",
+        PickupKind::RapidFire => "Assets/Images/pickup_rapid_fire.png",
+        PickupKind::SpreadShot => "Assets/Images/pickup_spread_shot.png",
+        PickupKind::ScoreBonus => "Assets/Images/pickup_score_bonus.png",
+    }
+}
+
+//Rolls the drop chance and spawns a random slowly drifting pickup where an asteroid was destroyed
+pub fn maybe_drop_pickup(lazy: &LazyUpdate, entities: &Entities, x: f64, y: f64, tuning: &Tuning) {
+    let mut rng = rand::thread_rng();
+    if rng.gen_range(0.0..1.0) >= tuning.pickup_drop_chance {
+        return;
+    }
+    let kind = ALL_PICKUPS[rng.gen_range(0..ALL_PICKUPS.len())];
+
+    build_pickup(lazy.create_entity(entities), x, y, kind, tuning.pickup_lifetime).build();
+}
+
+pub fn build_pickup<B: Builder>(builder: B, x: f64, y: f64, kind: PickupKind, lifetime: f64) -> B {
+    let mut rng = rand::thread_rng();
+    builder
+        .with(components::Position { x, y, rot: 0.0 })
+        .with(components::Renderable {
+            texture_name: texture_name(kind).to_string(),
+            img_width: 64,
+            img_height: 64,
+            output_width: PICKUP_SIZE,
+            output_height: PICKUP_SIZE,
+            img_rotation: 0.0,
+            visible: true
+        })
+        .with(components::Pickup { kind })
+        .with(crate::movement::velocity_from_heading(rng.gen_range(0.0..360.0), rng.gen_range(20.0..60.0)))
+        .with(components::AngularVelocity { speed: rng.gen_range(-90.0..90.0) })
+        .with(components::BoundaryBehavior::Bounce)
+        .with(components::Lifetime { remaining: lifetime })
+}
+
+pub struct PickupCollector;
+
+impl<'a> System<'a> for PickupCollector {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::Renderable>,
+        ReadStorage<'a, components::Pickup>,
+        WriteStorage<'a, components::Player>,
+        WriteStorage<'a, components::PowerUps>,
+        WriteStorage<'a, components::GameData>,
+        Entities<'a>,
+        ReadExpect<'a, Tuning>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, renderables, pickups, mut players, mut power_ups, mut game_data, entities, tuning) = data;
+
+        //Score bonuses and the player slot that collected them
+        let mut bonus_scores = Vec::new();
+        //Two players touching the same pickup in one frame must not both collect it
+        let mut collected = HashSet::new();
+        for (player_pos, player_renderable, player, power_ups) in (&positions, &renderables, &mut players, &mut power_ups).join() {
+            for (pickup_pos, pickup_renderable, pickup, pickup_entity) in (&positions, &renderables, &pickups, &entities).join() {
+                if collected.contains(&pickup_entity) {
+                    continue;
+                }
+                let diff_x = player_pos.x - pickup_pos.x;
+                let diff_y = player_pos.y - pickup_pos.y;
+                let touch_distance = (player_renderable.output_width + pickup_renderable.output_width) as f64 / 2.0;
+
+                if diff_x * diff_x + diff_y * diff_y < touch_distance * touch_distance {
+                    match pickup.kind {
+                        PickupKind::Repair => {
+                            player.health = (player.health + 1).min(player.max_health);
+                        },
+                        PickupKind::Shield => power_ups.shield = tuning.power_up_duration,
+                        PickupKind::RapidFire => power_ups.rapid_fire = tuning.power_up_duration,
+                        PickupKind::SpreadShot => power_ups.spread_shot = tuning.power_up_duration,
+                        PickupKind::ScoreBonus => bonus_scores.push(player.slot),
+                    }
+                    entities.delete(pickup_entity).ok();
+                    collected.insert(pickup_entity);
+                }
+            }
+        }
+
+        if !bonus_scores.is_empty() {
+            for data in (&mut game_data).join() {
+                for slot in &bonus_scores {
+                    data.add_score(Some(*slot), SCORE_BONUS * data.level);
+                }
+            }
+        }
+    }
+}
+
+pub struct PowerUpTimers;
+
+impl<'a> System<'a> for PowerUpTimers {
+    type SystemData = (
+        WriteStorage<'a, components::PowerUps>,
+        Read<'a, crate::DeltaTime>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (mut power_ups, delta_time) = data;
+
+        for power_ups in (&mut power_ups).join() {
+            power_ups.shield = (power_ups.shield - delta_time.0).max(0.0);
+            power_ups.rapid_fire = (power_ups.rapid_fire - delta_time.0).max(0.0);
+            power_ups.spread_shot = (power_ups.spread_shot - delta_time.0).max(0.0);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::components::WeaponStats;
+use crate::weapon;
+
+//Permanent improvements for the rest of the run, bought with credits between waves
+#[derive(Clone, Copy, PartialEq)]
+pub enum Upgrade {
+    MaxSpeed,
+    RotationSpeed,
+    MaxHealth,
+    FireRate,
+    LaserSpeed,
+}
+
+pub const ALL_UPGRADES: [Upgrade; 5] = [
+    Upgrade::MaxSpeed,
+    Upgrade::RotationSpeed,
+    Upgrade::MaxHealth,
+    Upgrade::FireRate,
+    Upgrade::LaserSpeed,
+];
+
+pub const MAX_RANK: u32 = 5;
+//Each rank costs this much more than the last
+const RANK_COST: u32 = 250;
+//Speed, rotation, fire rate and laser speed go up by this share of the base value per rank
+const RANK_SCALE: f64 = 0.1;
+
+impl Upgrade {
+    pub fn name(self) -> &'static str {
+        match self {
+            Upgrade::MaxSpeed => "Max Speed",
+            Upgrade::RotationSpeed => "Rotation Speed",
+            Upgrade::MaxHealth => "Max Health",
+            Upgrade::FireRate => "Fire Rate",
+            Upgrade::LaserSpeed => "Laser Speed",
+        }
+    }
+}
+
+//Ranks bought by one player, kept in GameData so they survive the ship being lost
+#[derive(Clone, Default)]
+pub struct Upgrades {
+    ranks: [u32; ALL_UPGRADES.len()],
+}
+
+impl Upgrades {
+    pub fn rank(&self, upgrade: Upgrade) -> u32 {
+        self.ranks[upgrade as usize]
+    }
+
+    //None once the upgrade is maxed out
+    pub fn cost(&self, upgrade: Upgrade) -> Option<u32> {
+        let rank = self.rank(upgrade);
+        if rank >= MAX_RANK { None } else { Some(RANK_COST * (rank + 1)) }
+    }
+
+    pub fn raise(&mut self, upgrade: Upgrade) {
+        let rank = &mut self.ranks[upgrade as usize];
+        *rank = (*rank + 1).min(MAX_RANK);
+    }
+
+    //Pays for the next rank out of credits. Refused when maxed out or the credits do not cover it
+    pub fn buy(&mut self, upgrade: Upgrade, credits: &mut u32) -> bool {
+        match self.cost(upgrade) {
+            Some(cost) if cost <= *credits => {
+                *credits -= cost;
+                self.raise(upgrade);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    //Multiplier on the base value for the percentage upgrades
+    pub fn scale(&self, upgrade: Upgrade) -> f64 {
+        1.0 + RANK_SCALE * self.rank(upgrade) as f64
+    }
+
+    //Extra health on top of the difficulty's maximum
+    pub fn bonus_health(&self) -> i32 {
+        self.rank(Upgrade::MaxHealth) as i32
+    }
+
+    //The default loadout with fire rate and laser speed upgrades applied
+    pub fn loadout(&self) -> Vec<WeaponStats> {
+        let mut loadout = weapon::default_loadout();
+        for stats in &mut loadout {
+            stats.fire_rate *= self.scale(Upgrade::FireRate);
+            stats.projectile_speed *= self.scale(Upgrade::LaserSpeed);
+        }
+        loadout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_and_scale_per_rank() {
+        let mut upgrades = Upgrades::default();
+        for rank in 0..MAX_RANK {
+            assert_eq!(upgrades.rank(Upgrade::FireRate), rank);
+            assert_eq!(upgrades.cost(Upgrade::FireRate), Some(250 * (rank + 1)));
+            assert!((upgrades.scale(Upgrade::FireRate) - (1.0 + 0.1 * rank as f64)).abs() < 1e-9);
+            upgrades.raise(Upgrade::FireRate);
+        }
+        assert_eq!(upgrades.cost(Upgrade::FireRate), None);
+        assert!((upgrades.scale(Upgrade::FireRate) - 1.5).abs() < 1e-9);
+
+        //Raising past the top rank changes nothing
+        upgrades.raise(Upgrade::FireRate);
+        assert_eq!(upgrades.rank(Upgrade::FireRate), MAX_RANK);
+        //Other upgrades are untouched
+        assert_eq!(upgrades.rank(Upgrade::LaserSpeed), 0);
+        assert_eq!(upgrades.scale(Upgrade::LaserSpeed), 1.0);
+    }
+
+    #[test]
+    fn health_and_loadout_follow_the_ranks() {
+        let mut upgrades = Upgrades::default();
+        upgrades.raise(Upgrade::MaxHealth);
+        upgrades.raise(Upgrade::MaxHealth);
+        upgrades.raise(Upgrade::FireRate);
+        assert_eq!(upgrades.bonus_health(), 2);
+
+        for (upgraded, base) in upgrades.loadout().iter().zip(weapon::default_loadout()) {
+            assert!((upgraded.fire_rate - base.fire_rate * 1.1).abs() < 1e-9);
+            assert_eq!(upgraded.projectile_speed, base.projectile_speed);
+        }
+    }
+
+    #[test]
+    fn buy_refuses_when_credits_are_short() {
+        let mut upgrades = Upgrades::default();
+        let mut credits = 249;
+        assert!(!upgrades.buy(Upgrade::MaxSpeed, &mut credits));
+        assert_eq!(credits, 249);
+        assert_eq!(upgrades.rank(Upgrade::MaxSpeed), 0);
+
+        credits = 700;
+        assert!(upgrades.buy(Upgrade::MaxSpeed, &mut credits));
+        assert_eq!(credits, 450);
+        assert!(!upgrades.buy(Upgrade::MaxSpeed, &mut credits));
+        assert_eq!(credits, 450);
+        assert_eq!(upgrades.rank(Upgrade::MaxSpeed), 1);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::components::BoundaryBehavior;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FireMode {
+    //One shot per press of the fire key
+    Tap,
+    //Keeps firing at the weapon's rate while the key is held
+    Hold,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameMode {
+    //Waves of asteroids, enemies and bosses
+    Standard,
+    //Two ships in an arena shooting each other, first to win enough rounds takes the match
+    Versus,
+    //Standard waves against the clock, the run ends when time is up
+    TimeAttack,
+    //Asteroids keep coming instead of arriving in waves, the score is seconds survived
+    Survival,
+    //Standard waves where nothing can hurt the ship, for practice
+    Zen,
+}
+
+pub const ALL_GAME_MODES: [GameMode; 5] = [GameMode::Standard, GameMode::Versus, GameMode::TimeAttack, GameMode::Survival, GameMode::Zen];
+
+impl GameMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Standard => "Standard",
+            GameMode::Versus => "Versus",
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::Survival => "Survival",
+            GameMode::Zen => "Zen",
+        }
+    }
+
+    //Spaces, dashes and underscores are ignored so time-attack and timeattack both work on the command line
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        let name = name.replace([' ', '-', '_'], "");
+        ALL_GAME_MODES.iter().copied().find(|mode| mode.name().replace(' ', "").eq_ignore_ascii_case(&name))
+    }
+
+    //Modes that play out in cleared waves with the shop in between
+    pub fn has_waves(self) -> bool {
+        matches!(self, GameMode::Standard | GameMode::TimeAttack | GameMode::Zen)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Insane,
+}
+
+pub const ALL_DIFFICULTIES: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Insane];
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Insane => "Insane",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        ALL_DIFFICULTIES.iter().copied().find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn starting_health(self) -> i32 {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Normal => 3,
+            Difficulty::Hard => 3,
+            Difficulty::Insane => 2,
+        }
+    }
+
+    //Most health repairs and wave heals can bring the ship up to
+    pub fn max_health(self) -> i32 {
+        match self {
+            Difficulty::Easy => 12,
+            Difficulty::Normal => 10,
+            Difficulty::Hard => 8,
+            Difficulty::Insane => 6,
+        }
+    }
+
+    pub fn asteroid_speed_scale(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.2,
+            Difficulty::Insane => 1.45,
+        }
+    }
+
+    pub fn asteroid_count_scale(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+            Difficulty::Insane => 1.5,
+        }
+    }
+
+    //Scales how fast enemies move, turn and shoot
+    pub fn enemy_aggression(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.7,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+            Difficulty::Insane => 1.6,
+        }
+    }
+
+    pub fn score_multiplier(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+            Difficulty::Insane => 2.0,
+        }
+    }
+}
+
+pub struct Tuning {
+    pub player_boundary: BoundaryBehavior,
+    pub asteroid_boundary: BoundaryBehavior,
+    pub laser_boundary: BoundaryBehavior,
+    pub laser_lifetime: f64,
+    //Screen shake, zoom and kick, can be turned off for players sensitive to motion
+    pub camera_effects: bool,
+    //Chance that a destroyed asteroid leaves a pickup behind
+    pub pickup_drop_chance: f64,
+    pub pickup_lifetime: f64,
+    pub power_up_duration: f64,
+    pub fire_mode: FireMode,
+    //Grace period after the player is hit and how fast the ship blinks during it
+    pub invulnerability_duration: f64,
+    pub invulnerability_blink_interval: f64,
+    //Speed the ship is thrown away from whatever hit it
+    pub knockback_speed: f64,
+    //Ships each player brings to the run, how long until a lost ship comes back and how much empty space it needs.
+    //All but each player's first ship go into a shared pool
+    pub starting_lives: u32,
+    pub respawn_delay: f64,
+    pub respawn_safe_radius: f64,
+    //Hyperspace jumps to a spot clear of asteroids, but can malfunction and drop the ship anywhere damaged
+    pub hyperspace_cooldown: f64,
+    pub hyperspace_malfunction_chance: f64,
+    //Difficulty new runs start on
+    pub difficulty: Difficulty,
+    //Two for local co-op
+    pub player_count: usize,
+    pub game_mode: GameMode,
+    pub versus_rounds_to_win: u32,
+    //Length of a time attack run in seconds
+    pub time_attack_duration: f64,
+    //Seconds between survival spawns at the start of a run, shrinking over time down to the minimum
+    pub survival_spawn_interval: f64,
+    pub survival_min_spawn_interval: f64,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning {
+            player_boundary: BoundaryBehavior::Wrap,
+            asteroid_boundary: BoundaryBehavior::Bounce,
+            laser_boundary: BoundaryBehavior::Despawn,
+            laser_lifetime: 4.0,
+            camera_effects: true,
+            pickup_drop_chance: 0.1,
+            pickup_lifetime: 12.0,
+            power_up_duration: 10.0,
+            fire_mode: FireMode::Tap,
+            invulnerability_duration: 1.5,
+            invulnerability_blink_interval: 0.1,
+            knockback_speed: 300.0,
+            starting_lives: 3,
+            respawn_delay: 2.0,
+            respawn_safe_radius: 400.0,
+            hyperspace_cooldown: 5.0,
+            hyperspace_malfunction_chance: 0.05,
+            difficulty: Difficulty::Normal,
+            player_count: 1,
+            game_mode: GameMode::Standard,
+            versus_rounds_to_win: 3,
+            time_attack_duration: 180.0,
+            survival_spawn_interval: 2.0,
+            survival_min_spawn_interval: 0.35,
+        }
+    }
+}
+
+impl Tuning {
+    //Everything wraps around the screen edges like the arcade original
+    pub fn classic() -> Self {
+        Tuning {
+            player_boundary: BoundaryBehavior::Wrap,
+            asteroid_boundary: BoundaryBehavior::Wrap,
+            laser_boundary: BoundaryBehavior::Wrap,
+            laser_lifetime: 1.5,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+use rand::Rng;
+
+use crate::{asteroid, boss, enemy, pickup};
+use crate::components::{AsteroidVariant, PickupKind};
+
+pub const WAVE_FILE: &str = "Assets/Levels/waves.txt";
+//Chance that a survival spawn is an enemy instead of an asteroid
+const SURVIVAL_ENEMY_CHANCE: f64 = 0.08;
+
+//What spawns when a level starts
+#[derive(Clone)]
+pub struct WaveDefinition {
+    pub asteroids: u32,
+    pub asteroid_size: Range<u32>,
+    pub asteroid_speed: Range<f64>,
+    //Share of the asteroids that are each special variant, the rest are plain
+    pub armoured: f64,
+    pub explosive: f64,
+    pub magnetic: f64,
+    pub enemies: u32,
+    pub pickups: u32,
+    //Black holes that stay until the wave is cleared
+    pub gravity_wells: u32,
+    //Seconds between each spawn, 0 brings the whole wave in at once
+    pub spawn_interval: f64,
+    //Health restored when the wave starts
+    pub heal: i32,
+    //Boss waves are only the boss
+    pub boss: bool,
+}
+
+impl WaveDefinition {
+    //Used for every level the wave file does not describe
+    pub fn endless(level: u32) -> Self {
+        WaveDefinition {
+            asteroids: level * 2,
+            asteroid_size: asteroid::ASTEROID_MIN_SIZE..asteroid::ASTEROID_MAX_SIZE,
+            asteroid_speed: 70.0..250.0,
+            //Each variant starts turning up a couple of levels after the last
+            armoured: ((level as f64 - 3.0) * 0.04).clamp(0.0, 0.3),
+            explosive: ((level as f64 - 5.0) * 0.03).clamp(0.0, 0.2),
+            magnetic: ((level as f64 - 7.0) * 0.03).clamp(0.0, 0.2),
+            enemies: enemy::enemies_for_level(level),
+            pickups: 0,
+            gravity_wells: if level >= 7 { ((level - 3) / 4).min(3) } else { 0 },
+            spawn_interval: 0.0,
+            heal: 1,
+            boss: boss::is_boss_level(level),
+        }
+    }
+
+    fn roll_variant(&self, rng: &mut impl Rng) -> AsteroidVariant {
+        let roll = rng.gen_range(0.0..1.0);
+        if roll < self.armoured {
+            AsteroidVariant::Armoured
+        } else if roll < self.armoured + self.explosive {
+            AsteroidVariant::Explosive
+        } else if roll < self.armoured + self.explosive + self.magnetic {
+            AsteroidVariant::Magnetic
+        } else {
+            AsteroidVariant::Plain
+        }
+    }
+}
+
+//Authored waves by level
+#[derive(Default)]
+pub struct Waves {
+    pub levels: HashMap<u32, WaveDefinition>,
+}
+
+impl Waves {
+    pub fn definition_for(&self, level: u32) -> WaveDefinition {
+        self.levels.get(&level).cloned().unwrap_or_else(|| WaveDefinition::endless(level))
+    }
+}
+
+//Loads the wave file. Each wave starts with a [level N] header followed by key = value lines,
+//anything left out falls back to the endless formula for that level. # starts a comment.
+//Without a wave file every level is endless, only a broken one is an error
+pub fn load_waves(path: &str) -> Result<Waves, String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Could not read wave file, using endless waves: {}: {}", path, e);
+            return Ok(Waves::default());
+        }
+    };
+    parse_waves(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+pub fn parse_waves(text: &str) -> Result<Waves, String> {
+    let mut waves = Waves::default();
+    //Level being filled in and the line of its header
+    let mut current: Option<(u32, usize)> = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            let level = header.trim().strip_prefix("level")
+                .and_then(|number| number.trim().parse::<u32>().ok())
+                .ok_or(format!("line {}: expected [level N], found [{}]", line_number, header))?;
+            if let Some((previous, header_line)) = current {
+                check_wave(&waves.levels[&previous], header_line)?;
+            }
+            waves.levels.insert(level, WaveDefinition::endless(level));
+            current = Some((level, line_number));
+            continue;
+        }
+
+        let (level, _) = current.ok_or(format!("line {}: setting outside of a [level N] block", line_number))?;
+        let (key, value) = line.split_once('=').ok_or(format!("line {}: expected key = value", line_number))?;
+        let (key, value) = (key.trim(), value.trim());
+        let wave = waves.levels.get_mut(&level).expect("level inserted by its header");
+        let invalid = || format!("line {}: invalid value '{}' for {}", line_number, value, key);
+
+        match key {
+            "asteroids" => wave.asteroids = value.parse().map_err(|_| invalid())?,
+            "asteroid_size" => wave.asteroid_size = parse_range(value).ok_or_else(invalid)?,
+            "asteroid_speed" => wave.asteroid_speed = parse_range(value).ok_or_else(invalid)?,
+            "armoured" => wave.armoured = parse_share(value).ok_or_else(invalid)?,
+            "explosive" => wave.explosive = parse_share(value).ok_or_else(invalid)?,
+            "magnetic" => wave.magnetic = parse_share(value).ok_or_else(invalid)?,
+            "enemies" => wave.enemies = value.parse().map_err(|_| invalid())?,
+            "pickups" => wave.pickups = value.parse().map_err(|_| invalid())?,
+            "gravity_wells" => wave.gravity_wells = value.parse().map_err(|_| invalid())?,
+            "spawn_interval" => wave.spawn_interval = value.parse().map_err(|_| invalid())?,
+            "heal" => wave.heal = value.parse().map_err(|_| invalid())?,
+            "boss" => wave.boss = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("line {}: unknown setting '{}'", line_number, key)),
+        }
+    }
+    if let Some((level, header_line)) = current {
+        check_wave(&waves.levels[&level], header_line)?;
+    }
+    Ok(waves)
+}
+
+//A wave with nothing to destroy would count as cleared the moment it started,
+//and variant shares adding up to more than 1 would leave the later variants short
+fn check_wave(wave: &WaveDefinition, header_line: usize) -> Result<(), String> {
+    if wave.asteroids == 0 && wave.enemies == 0 && !wave.boss {
+        return Err(format!("line {}: wave has no asteroids, enemies or boss to clear", header_line));
+    }
+    if wave.asteroid_size.start < asteroid::ASTEROID_MIN_SIZE || wave.asteroid_size.end > asteroid::ASTEROID_MAX_SIZE {
+        return Err(format!("line {}: asteroid_size must be within {}..{}", header_line, asteroid::ASTEROID_MIN_SIZE, asteroid::ASTEROID_MAX_SIZE));
+    }
+    //0 is fine and brings the whole wave in at once
+    if !wave.spawn_interval.is_finite() || wave.spawn_interval < 0.0 {
+        return Err(format!("line {}: spawn_interval must be 0 or more seconds", header_line));
+    }
+    //Healing a negative amount could leave a ship alive with no health
+    if wave.heal < 0 {
+        return Err(format!("line {}: heal must not be negative", header_line));
+    }
+    let shares = wave.armoured + wave.explosive + wave.magnetic;
+    //Small slack so shares meant to add up to exactly 1 are not refused over rounding
+    if shares > 1.0 + 1e-9 {
+        return Err(format!("line {}: armoured, explosive and magnetic add up to {}, more than 1", header_line, shares));
+    }
+    Ok(())
+}
+
+//Parses min..max, the range must not be empty since it is sampled from
+fn parse_range<T: std::str::FromStr + PartialOrd>(value: &str) -> Option<Range<T>> {
+    let (min, max) = value.split_once("..")?;
+    let range = min.trim().parse().ok()?..max.trim().parse().ok()?;
+    if range.is_empty() { None } else { Some(range) }
+}
+
+//A fraction between 0 and 1
+fn parse_share(value: &str) -> Option<f64> {
+    value.parse().ok().filter(|share| (0.0..=1.0).contains(share))
+}
+
+pub enum WaveSpawn {
+    Asteroid { size: u32, speed: f64, rotation_speed: f64, variant: AsteroidVariant },
+    Enemy,
+    Pickup(PickupKind),
+    //Drifting wells wander slowly, the rest stay where they appear
+    GravityWell { drifting: bool },
+}
+
+//Spawns of the current wave still waiting to come in
+#[derive(Default)]
+pub struct WaveSpawner {
+    pub queue: VecDeque<WaveSpawn>,
+    pub interval: f64,
+    pub timer: f64,
+}
+
+impl WaveSpawner {
+    //Rolls everything the wave will spawn up front and queues it
+    pub fn queue_wave(&mut self, wave: &WaveDefinition) {
+        let mut rng = rand::thread_rng();
+        for _ in 0..wave.pickups {
+            self.queue.push_back(WaveSpawn::Pickup(pickup::ALL_PICKUPS[rng.gen_range(0..pickup::ALL_PICKUPS.len())]));
+        }
+        for _ in 0..wave.gravity_wells {
+            self.queue.push_back(WaveSpawn::GravityWell { drifting: rng.gen_bool(0.5) });
+        }
+        for _ in 0..wave.asteroids {
+            self.queue.push_back(roll_asteroid(wave, &mut rng));
+        }
+        for _ in 0..wave.enemies {
+            self.queue.push_back(WaveSpawn::Enemy);
+        }
+        self.interval = wave.spawn_interval.max(0.0);
+        self.timer = 0.0;
+    }
+
+    //Survival has no waves, a single asteroid or now and then an enemy is kept waiting instead
+    pub fn trickle(&mut self, wave: &WaveDefinition, interval: f64) {
+        //An interval of 0 would release everything at once
+        self.interval = interval.max(0.01);
+        if !self.queue.is_empty() {
+            return;
+        }
+        let mut rng = rand::thread_rng();
+        if rng.gen_bool(SURVIVAL_ENEMY_CHANCE) {
+            self.queue.push_back(WaveSpawn::Enemy);
+        } else {
+            self.queue.push_back(roll_asteroid(wave, &mut rng));
+        }
+    }
+
+    //Spawns that are due this frame
+    pub fn release(&mut self, delta_time: f64) -> Vec<WaveSpawn> {
+        if self.interval <= 0.0 {
+            return self.queue.drain(..).collect();
+        }
+        let mut due = Vec::new();
+        self.timer -= delta_time;
+        while self.timer <= 0.0 {
+            match self.queue.pop_front() {
+                Some(spawn) => due.push(spawn),
+                None => break,
+            }
+            self.timer += self.interval;
+        }
+        due
+    }
+}
+
+fn roll_asteroid(wave: &WaveDefinition, rng: &mut impl Rng) -> WaveSpawn {
+    WaveSpawn::Asteroid {
+        size: rng.gen_range(wave.asteroid_size.clone()),
+        speed: rng.gen_range(wave.asteroid_speed.clone()),
+        rotation_speed: rng.gen_range(-400.0..400.0),
+        variant: wave.roll_variant(rng),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_valid_file() {
+        let waves = parse_waves("
+            # Opening wave
+            [level 2]
+            asteroids = 4
+            asteroid_size = 50..100
+            asteroid_speed = 70..180
+            armoured = 0.25
+            explosive = 0.25
+            magnetic = 0.5
+            enemies = 1
+            pickups = 2
+            gravity_wells = 1
+            spawn_interval = 0.5
+            heal = 2
+
+            [level 5]
+            boss = true
+        ").unwrap();
+
+        assert_eq!(waves.levels.len(), 2);
+        let wave = &waves.levels[&2];
+        assert_eq!(wave.asteroids, 4);
+        assert_eq!(wave.asteroid_size, 50..100);
+        assert_eq!(wave.asteroid_speed, 70.0..180.0);
+        assert_eq!((wave.armoured, wave.explosive, wave.magnetic), (0.25, 0.25, 0.5));
+        assert_eq!((wave.enemies, wave.pickups, wave.gravity_wells), (1, 2, 1));
+        assert_eq!(wave.spawn_interval, 0.5);
+        assert_eq!(wave.heal, 2);
+        assert!(waves.levels[&5].boss);
+    }
+
+    #[test]
+    fn missing_settings_fall_back_to_endless() {
+        let waves = parse_waves("[level 8]\nasteroids = 3\n").unwrap();
+        let wave = waves.definition_for(8);
+        let endless = WaveDefinition::endless(8);
+
+        assert_eq!(wave.asteroids, 3);
+        assert_eq!(wave.asteroid_size, endless.asteroid_size);
+        assert_eq!(wave.asteroid_speed, endless.asteroid_speed);
+        assert_eq!((wave.armoured, wave.explosive, wave.magnetic), (endless.armoured, endless.explosive, endless.magnetic));
+        assert_eq!((wave.enemies, wave.pickups, wave.gravity_wells), (endless.enemies, endless.pickups, endless.gravity_wells));
+        assert_eq!(wave.heal, endless.heal);
+        assert_eq!(wave.boss, endless.boss);
+
+        //Levels the file leaves out are endless altogether
+        assert_eq!(waves.definition_for(9).asteroids, WaveDefinition::endless(9).asteroids);
+    }
+
+    #[test]
+    fn shipped_wave_file_parses() {
+        parse_waves(include_str!("../Assets/Levels/waves.txt")).unwrap();
+    }
+
+    fn error_for(text: &str) -> String {
+        match parse_waves(text) {
+            Ok(_) => panic!("accepted {:?}", text),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert!(error_for("asteroids = 3").starts_with("line 1:"));
+        assert!(error_for("[wave 2]").starts_with("line 1:"));
+        assert!(error_for("[level 2]\nasteroids").starts_with("line 2:"));
+        assert!(error_for("[level 2]\ncomets = 3").starts_with("line 2:"));
+        assert!(error_for("[level 2]\nasteroids = -1").starts_with("line 2:"));
+        assert!(error_for("[level 2]\nasteroid_speed = 200..100").starts_with("line 2:"));
+        assert!(error_for("[level 2]\narmoured = 1.5").starts_with("line 2:"));
+    }
+
+    #[test]
+    fn rejects_a_wave_with_nothing_to_clear() {
+        let error = error_for("[level 2]\nasteroids = 4\n\n[level 3]\nasteroids = 0\nenemies = 0\npickups = 2\n");
+        assert!(error.starts_with("line 4:"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_variant_shares_above_one() {
+        assert!(error_for("[level 2]\narmoured = 0.5\nexplosive = 0.4\nmagnetic = 0.2").starts_with("line 1:"));
+        assert!(parse_waves("[level 2]\narmoured = 0.3\nexplosive = 0.3\nmagnetic = 0.4").is_ok());
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        assert!(error_for("[level 2]\nasteroid_size = 20..60").starts_with("line 1:"));
+        assert!(error_for("[level 2]\nasteroid_size = 60..150").starts_with("line 1:"));
+        assert!(error_for("[level 2]\nspawn_interval = -0.5").starts_with("line 1:"));
+        assert!(error_for("[level 2]\nspawn_interval = NaN").starts_with("line 1:"));
+        assert!(error_for("[level 2]\nheal = -1").starts_with("line 1:"));
+    }
+}
@@ -0,0 +1,238 @@
+use specs::prelude::*;
+use specs::{Entities, Join};
+
+use crate::components;
+use crate::components::{Faction, ProjectilePrefab, Weapon, WeaponKind, WeaponStats};
+use crate::movement;
+use crate::achievements::{GameEvent, GameEvents};
+use crate::tuning::Tuning;
+
+//Players and enemies each have their own budget so enemy volleys can never block the player's shots
+const MAX_LASERS_PER_FACTION: usize = 200;
+//Rapid fire power up multiplies the fire rate of whatever weapon is selected
+const RAPID_FIRE_MULTIPLIER: f64 = 2.0;
+//Angle between the extra lasers of the spread shot power up and the centre one
+const SPREAD_SHOT_ANGLE: f64 = 15.0;
+//Most time in seconds a held trigger can fall behind before shots are dropped
+const MAX_CATCH_UP: f64 = 0.25;
+
+const LASER: ProjectilePrefab = ProjectilePrefab {
+    texture_name: "Assets/Images/laser.png",
+    img_width: 64,
+    img_height: 153,
+    output_width: 20,
+    output_height: 50,
+    lifetime: None,
+    pierce: 0
+};
+
+pub fn default_loadout() -> Vec<WeaponStats> {
+    vec![
+        WeaponStats {
+            kind: WeaponKind::Single,
+            name: "Laser",
+            fire_rate: 8.0,
+            projectile: LASER,
+            projectile_speed: 500.0,
+            projectiles_per_shot: 1,
+            spread: 0.0,
+            burst_count: 1,
+            burst_interval: 0.0
+        },
+        WeaponStats {
+            kind: WeaponKind::Spread,
+            name: "Spread",
+            fire_rate: 2.5,
+            projectile: ProjectilePrefab { lifetime: Some(1.2), ..LASER },
+            projectile_speed: 450.0,
+            projectiles_per_shot: 5,
+            spread: 12.0,
+            burst_count: 1,
+            burst_interval: 0.0
+        },
+        WeaponStats {
+            kind: WeaponKind::Rapid,
+            name: "Rapid",
+            fire_rate: 4.0,
+            projectile: ProjectilePrefab { output_width: 14, output_height: 35, ..LASER },
+            projectile_speed: 650.0,
+            projectiles_per_shot: 1,
+            spread: 0.0,
+            burst_count: 3,
+            burst_interval: 0.06
+        },
+        WeaponStats {
+            kind: WeaponKind::PiercingBeam,
+            name: "Piercing Beam",
+            fire_rate: 1.5,
+            projectile: ProjectilePrefab { output_width: 16, output_height: 120, pierce: 5, ..LASER },
+            projectile_speed: 1200.0,
+            projectiles_per_shot: 1,
+            spread: 0.0,
+            burst_count: 1,
+            burst_interval: 0.0
+        },
+    ]
+}
+
+pub const ENEMY_LASER: ProjectilePrefab = ProjectilePrefab {
+    texture_name: "Assets/Images/enemy_laser.png",
+    lifetime: Some(3.0),
+    ..LASER
+};
+
+//Slow, easy to dodge shots fired by enemy ships
+pub fn enemy_loadout() -> Vec<WeaponStats> {
+    vec![
+        WeaponStats {
+            kind: WeaponKind::Single,
+            name: "Enemy Laser",
+            fire_rate: 0.8,
+            projectile: ENEMY_LASER,
+            projectile_speed: 380.0,
+            projectiles_per_shot: 1,
+            spread: 0.0,
+            burst_count: 1,
+            burst_interval: 0.0
+        },
+    ]
+}
+
+impl Weapon {
+    pub fn new(loadout: Vec<WeaponStats>) -> Self {
+        Weapon {
+            loadout,
+            selected: 0,
+            trigger: false,
+            aim: None,
+            cooldown: 0.0,
+            burst_remaining: 0,
+            burst_timer: 0.0
+        }
+    }
+
+    pub fn current(&self) -> &WeaponStats {
+        &self.loadout[self.selected]
+    }
+
+    //Switching cancels any burst in progress but keeps the cooldown so switching cannot skip it
+    pub fn select(&mut self, index: usize) {
+        if index < self.loadout.len() && index != self.selected {
+            self.selected = index;
+            self.burst_remaining = 0;
+        }
+    }
+}
+
+pub struct WeaponSystem;
+
+impl<'a> System<'a> for WeaponSystem {
+    type SystemData = (
+        ReadStorage<'a, components::Position>,
+        WriteStorage<'a, Weapon>,
+        ReadStorage<'a, components::PowerUps>,
+        ReadStorage<'a, components::Enemy>,
+        ReadStorage<'a, components::Laser>,
+        ReadStorage<'a, components::Player>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        ReadExpect<'a, Tuning>,
+        Read<'a, crate::DeltaTime>,
+        Write<'a, GameEvents>,
+    );
+    fn run (&mut self, data: Self::SystemData) {
+        let (positions, mut weapons, power_ups, enemies, lasers, players, entities, lazy, tuning, delta_time, mut events) = data;
+        let delta_time = delta_time.0;
+
+        let mut player_laser_count = lasers.join().filter(|laser| laser.faction == Faction::Player).count();
+        let mut enemy_laser_count = lasers.join().count() - player_laser_count;
+
+        for (pos, weapon, power_ups, enemy, player) in (&positions, &mut weapons, power_ups.maybe(), enemies.maybe(), players.maybe()).join() {
+            let faction = if enemy.is_some() { Faction::Enemy } else { Faction::Player };
+            let owner = player.map(|player| player.slot);
+            let laser_count = if faction == Faction::Player { &mut player_laser_count } else { &mut enemy_laser_count };
+            let rapid_fire = power_ups.is_some_and(|power_ups| power_ups.rapid_fire > 0.0);
+            let spread_shot = power_ups.is_some_and(|power_ups| power_ups.spread_shot > 0.0);
+
+            let fire_rate = weapon.current().fire_rate * if rapid_fire { RAPID_FIRE_MULTIPLIER } else { 1.0 };
+
+            //Timers are allowed to go negative within a frame so several shots can come out of one
+            //long frame, keeping the rate of fire the same at any frame rate
+            //A long hitch can only catch up on a limited number of shots
+            weapon.cooldown = (weapon.cooldown - delta_time).max(-MAX_CATCH_UP);
+            weapon.burst_timer = (weapon.burst_timer - delta_time).max(-MAX_CATCH_UP);
+
+            loop {
+                if weapon.burst_remaining > 0 && weapon.burst_timer <= 0.0 {
+                    //How long ago within this frame the shot should have left the barrel
+                    let lag = (-weapon.burst_timer).min(delta_time);
+                    weapon.burst_remaining -= 1;
+                    weapon.burst_timer += weapon.current().burst_interval;
+
+                    let stats = weapon.current();
+                    let mut angles: Vec<f64> = (0..stats.projectiles_per_shot)
+                        .map(|i| (i as f64 - (stats.projectiles_per_shot - 1) as f64 / 2.0) * stats.spread)
+                        .collect();
+                    if spread_shot {
+                        angles.push(-SPREAD_SHOT_ANGLE);
+                        angles.push(SPREAD_SHOT_ANGLE);
+                    }
+
+                    for angle in angles {
+                        if *laser_count >= MAX_LASERS_PER_FACTION {
+                            break;
+                        }
+                        *laser_count += 1;
+                        let origin = components::Position { x: pos.x, y: pos.y, rot: weapon.aim.unwrap_or(pos.rot) + angle };
+                        spawn_projectile(&lazy, &entities, &origin, lag, stats, faction, owner, &tuning);
+                        if faction == Faction::Player {
+                            events.push(GameEvent::ShotFired);
+                        }
+                    }
+                }
+                else if weapon.burst_remaining == 0 && weapon.trigger && weapon.cooldown <= 0.0 {
+                    weapon.burst_remaining = weapon.current().burst_count;
+                    weapon.burst_timer = weapon.burst_timer.min(weapon.cooldown);
+                    weapon.cooldown += 1.0 / fire_rate;
+                }
+                else {
+                    break;
+                }
+            }
+            weapon.trigger = false;
+
+            //Never bank shots while idle
+            if weapon.burst_remaining == 0 {
+                weapon.cooldown = weapon.cooldown.max(0.0);
+                weapon.burst_timer = weapon.burst_timer.max(0.0);
+            }
+        }
+    }
+}
+
+//lag moves the projectile forward by the time it has already been flying this frame
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_projectile(lazy: &LazyUpdate, entities: &Entities, origin: &components::Position, lag: f64, stats: &WeaponStats, faction: Faction, owner: Option<usize>, tuning: &Tuning) {
+    let prefab = &stats.projectile;
+    let rot = origin.rot;
+    let velocity = movement::velocity_from_heading(rot, stats.projectile_speed);
+    let x = origin.x + velocity.x * lag;
+    let y = origin.y + velocity.y * lag;
+
+    lazy.create_entity(entities)
+        .with(components::Position { x, y, rot })
+        .with(components::Renderable {
+            texture_name: prefab.texture_name.to_string(),
+            img_width: prefab.img_width,
+            img_height: prefab.img_height,
+            output_width: prefab.output_width,
+            output_height: prefab.output_height,
+            img_rotation: rot,
+            visible: true
+        })
+        .with(components::Laser { pierce: prefab.pierce, faction, hit: false, owner })
+        .with(velocity)
+        .with(tuning.laser_boundary)
+        .with(components::Lifetime { remaining: prefab.lifetime.unwrap_or(tuning.laser_lifetime) })
+    .build();
+}